@@ -0,0 +1,165 @@
+use std::fmt::Write;
+
+use crate::vm::{Heap, Object, Value};
+
+use super::{Chunk, OpCode};
+
+impl Chunk {
+    /// Lists every instruction of the chunk under a `== name ==` header,
+    /// followed by the chunks of the functions it defines.
+    pub fn disassemble(&self, name: &str, heap: &Heap) -> String {
+        let mut out = format!("== {name} ==\n");
+
+        let mut offset = 0;
+        while offset < self.code.len() {
+            offset = self.disassemble_instruction(offset, heap, &mut out);
+        }
+
+        for constant in &self.constants {
+            if let Value::Object(handle) = constant {
+                if let Object::Function(function) = heap.get(*handle) {
+                    let name = function.name.as_deref().unwrap_or("<script>");
+                    out.push('\n');
+                    out.push_str(&function.chunk.disassemble(name, heap));
+                }
+            }
+        }
+
+        out
+    }
+
+    /// Writes the instruction at `offset` and returns the offset of the next
+    /// one. The line is replaced by `|` when it is the same as the previous
+    /// instruction's.
+    pub fn disassemble_instruction(&self, offset: usize, heap: &Heap, out: &mut String) -> usize {
+        let _ = write!(out, "{offset:04} ");
+
+        let line = self.spans[offset].line;
+        if offset > 0 && line == self.spans[offset - 1].line {
+            out.push_str("   | ");
+        } else {
+            let _ = write!(out, "{line:4} ");
+        }
+
+        let op = match OpCode::try_from(self.code[offset]) {
+            Ok(op) => op,
+            Err(byte) => {
+                let _ = writeln!(out, "Unknown opcode {byte}");
+                return offset + 1;
+            }
+        };
+        let name = op.to_string();
+
+        match op {
+            OpCode::Constant
+            | OpCode::GetGlobal
+            | OpCode::DefineGlobal
+            | OpCode::SetGlobal
+            | OpCode::GetProperty
+            | OpCode::SetProperty
+            | OpCode::GetSuper
+            | OpCode::Class
+            | OpCode::Method => self.constant_instruction(&name, offset, heap, out),
+            OpCode::GetLocal
+            | OpCode::SetLocal
+            | OpCode::GetUpvalue
+            | OpCode::SetUpvalue
+            | OpCode::Call
+            | OpCode::Interpolate => {
+                let _ = writeln!(out, "{name:<16} {:4}", self.code[offset + 1]);
+                offset + 2
+            }
+            OpCode::Jump | OpCode::JumpIfFalse => self.jump_instruction(&name, 1, offset, out),
+            OpCode::Loop => self.jump_instruction(&name, -1, offset, out),
+            OpCode::Invoke | OpCode::SuperInvoke => {
+                let constant = self.code[offset + 1];
+                let argument_count = self.code[offset + 2];
+                let value = heap.display(self.constants[constant as usize]);
+                let _ = writeln!(
+                    out,
+                    "{name:<16} ({argument_count} args) {constant:4} '{value}'"
+                );
+                offset + 3
+            }
+            OpCode::Closure => self.closure_instruction(&name, offset, heap, out),
+            OpCode::Nil
+            | OpCode::True
+            | OpCode::False
+            | OpCode::Pop
+            | OpCode::Equal
+            | OpCode::Greater
+            | OpCode::Less
+            | OpCode::Add
+            | OpCode::Subtract
+            | OpCode::Multiply
+            | OpCode::Divide
+            | OpCode::Not
+            | OpCode::Negate
+            | OpCode::Print
+            | OpCode::CloseUpvalue
+            | OpCode::Return
+            | OpCode::Inherit => {
+                let _ = writeln!(out, "{name}");
+                offset + 1
+            }
+        }
+    }
+
+    fn constant_instruction(
+        &self,
+        name: &str,
+        offset: usize,
+        heap: &Heap,
+        out: &mut String,
+    ) -> usize {
+        let constant = self.code[offset + 1];
+        let value = heap.display(self.constants[constant as usize]);
+        let _ = writeln!(out, "{name:<16} {constant:4} '{value}'");
+
+        offset + 2
+    }
+
+    /// `sign` is -1 for `Loop`, which jumps backwards.
+    fn jump_instruction(&self, name: &str, sign: isize, offset: usize, out: &mut String) -> usize {
+        let jump = self.read_u16(offset + 1) as isize;
+        let target = offset as isize + 3 + sign * jump;
+        let _ = writeln!(out, "{name:<16} {offset:4} -> {target}");
+
+        offset + 3
+    }
+
+    /// A closure is followed by one pair of bytes per captured variable.
+    fn closure_instruction(
+        &self,
+        name: &str,
+        offset: usize,
+        heap: &Heap,
+        out: &mut String,
+    ) -> usize {
+        let constant = self.code[offset + 1];
+        let function = self.constants[constant as usize];
+        let _ = writeln!(out, "{name:<16} {constant:4} {}", heap.display(function));
+
+        let upvalue_count = match function {
+            Value::Object(handle) => match heap.get(handle) {
+                Object::Function(function) => function.upvalue_count,
+                _ => 0,
+            },
+            _ => 0,
+        };
+
+        let mut offset = offset + 2;
+        for _ in 0..upvalue_count {
+            let kind = if self.code[offset] == 1 {
+                "local"
+            } else {
+                "upvalue"
+            };
+            let index = self.code[offset + 1];
+            let _ = writeln!(out, "{offset:04}      |                     {kind} {index}");
+            offset += 2;
+        }
+
+        offset
+    }
+}
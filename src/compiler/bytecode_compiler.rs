@@ -0,0 +1,697 @@
+use std::rc::Rc;
+
+use crate::{
+    chunk::{Chunk, OpCode},
+    expr::{
+        binary::BinaryOperator,
+        expression::Expr,
+        logical::LogicalOperator,
+        statement::{ClassDecl, FunctionDecl, Stmt},
+        unary::UnaryOperator,
+    },
+    primitives::{Literal, Span},
+    vm::{Function, Heap, ObjRef, Object, Value},
+};
+
+use super::{Error, Result};
+
+/// Slots, constants and upvalues are addressed with a single byte.
+const MAX_OPERAND: usize = u8::MAX as usize + 1;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FunctionKind {
+    Script,
+    Function,
+    Method,
+    Initializer,
+}
+
+struct Local {
+    name: String,
+    /// `None` while the initializer of the variable is being compiled.
+    depth: Option<usize>,
+    is_captured: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct UpvalueSlot {
+    index: u8,
+    is_local: bool,
+}
+
+/// The function currently being compiled, one per level of nesting.
+struct FunctionState {
+    chunk: Chunk,
+    name: Option<String>,
+    arity: usize,
+    kind: FunctionKind,
+    locals: Vec<Local>,
+    upvalues: Vec<UpvalueSlot>,
+    scope_depth: usize,
+}
+
+impl FunctionState {
+    fn new(kind: FunctionKind, name: Option<String>) -> Self {
+        // Slot zero holds the callee, which methods see as `this`.
+        let receiver = match kind {
+            FunctionKind::Method | FunctionKind::Initializer => "this",
+            FunctionKind::Script | FunctionKind::Function => "",
+        };
+
+        Self {
+            chunk: Chunk::default(),
+            name,
+            arity: 0,
+            kind,
+            locals: vec![Local {
+                name: receiver.to_string(),
+                depth: Some(0),
+                is_captured: false,
+            }],
+            upvalues: Vec::new(),
+            scope_depth: 0,
+        }
+    }
+}
+
+/// Compiles a resolved program into bytecode. Variables are resolved again
+/// here, into stack slots and upvalues instead of environment depths.
+pub struct Compiler<'h> {
+    heap: &'h mut Heap,
+    functions: Vec<FunctionState>,
+    /// The span of the last instruction, used for the ones no single node
+    /// is responsible for, like the pops at the end of a block.
+    last_span: Span,
+}
+
+impl<'h> Compiler<'h> {
+    pub fn new(heap: &'h mut Heap) -> Self {
+        Self {
+            heap,
+            functions: vec![FunctionState::new(FunctionKind::Script, None)],
+            last_span: Span::default(),
+        }
+    }
+
+    /// Returns the function object of the top level script.
+    pub fn compile(mut self, program: &[Stmt]) -> Result<ObjRef> {
+        for stmt in program {
+            self.statement(stmt)?;
+        }
+
+        let (script, _) = self.end_function();
+
+        Ok(script)
+    }
+
+    fn state(&mut self) -> &mut FunctionState {
+        self.functions
+            .last_mut()
+            .expect("There is always a function being compiled.")
+    }
+
+    fn chunk(&mut self) -> &mut Chunk {
+        &mut self.state().chunk
+    }
+
+    fn emit_byte(&mut self, byte: u8, span: Span) {
+        self.last_span = span;
+        self.chunk().write(byte, span);
+    }
+
+    fn emit(&mut self, op: OpCode, span: Span) {
+        self.last_span = span;
+        self.chunk().write_op(op, span);
+    }
+
+    fn emit_with_operand(&mut self, op: OpCode, operand: u8, span: Span) {
+        self.emit(op, span);
+        self.emit_byte(operand, span);
+    }
+
+    /// Identical constants share a slot. Strings are interned, so the same
+    /// name or literal used twice is found here too.
+    fn make_constant(&mut self, value: Value, span: Span) -> Result<u8> {
+        let existing = self.chunk().constants.iter().position(|c| *c == value);
+        let index = existing.unwrap_or_else(|| self.chunk().add_constant(value));
+        u8::try_from(index).map_err(|_| Error::TooManyConstants { span })
+    }
+
+    fn emit_constant(&mut self, value: Value, span: Span) -> Result<()> {
+        let constant = self.make_constant(value, span)?;
+        self.emit_with_operand(OpCode::Constant, constant, span);
+
+        Ok(())
+    }
+
+    fn identifier_constant(&mut self, name: &str, span: Span) -> Result<u8> {
+        let string = self.heap.intern(name);
+        self.make_constant(Value::Object(string), span)
+    }
+
+    /// Emits a jump with a placeholder offset and returns where the offset is
+    /// so it can be patched once the target is known.
+    fn emit_jump(&mut self, op: OpCode, span: Span) -> usize {
+        self.emit(op, span);
+        self.emit_byte(0xff, span);
+        self.emit_byte(0xff, span);
+
+        self.chunk().code.len() - 2
+    }
+
+    fn patch_jump(&mut self, offset: usize, span: Span) -> Result<()> {
+        let jump = self.chunk().code.len() - offset - 2;
+        let jump = u16::try_from(jump).map_err(|_| Error::JumpTooLarge { span })?;
+
+        self.chunk().code[offset..offset + 2].copy_from_slice(&jump.to_be_bytes());
+
+        Ok(())
+    }
+
+    fn emit_loop(&mut self, loop_start: usize, span: Span) -> Result<()> {
+        self.emit(OpCode::Loop, span);
+
+        let offset = self.chunk().code.len() - loop_start + 2;
+        let offset = u16::try_from(offset).map_err(|_| Error::LoopTooLarge { span })?;
+        let [high, low] = offset.to_be_bytes();
+        self.emit_byte(high, span);
+        self.emit_byte(low, span);
+
+        Ok(())
+    }
+
+    fn emit_return(&mut self, span: Span) {
+        if self.state().kind == FunctionKind::Initializer {
+            self.emit_with_operand(OpCode::GetLocal, 0, span);
+        } else {
+            self.emit(OpCode::Nil, span);
+        }
+        self.emit(OpCode::Return, span);
+    }
+
+    /// Finishes the innermost function and moves it to the heap. Returns its
+    /// handle and the upvalues the enclosing function has to capture for it.
+    fn end_function(&mut self) -> (ObjRef, Vec<UpvalueSlot>) {
+        self.emit_return(self.last_span);
+
+        let state = self
+            .functions
+            .pop()
+            .expect("There is always a function being compiled.");
+
+        let function = self.heap.alloc(Object::Function(Function {
+            arity: state.arity,
+            upvalue_count: state.upvalues.len(),
+            chunk: Rc::new(state.chunk),
+            name: state.name,
+        }));
+
+        (function, state.upvalues)
+    }
+
+    fn begin_scope(&mut self) {
+        self.state().scope_depth += 1;
+    }
+
+    fn end_scope(&mut self) {
+        let span = self.last_span;
+        let state = self.state();
+        state.scope_depth -= 1;
+        let depth = state.scope_depth;
+
+        while let Some(local) = self.state().locals.pop() {
+            if local.depth.is_some_and(|d| d <= depth) {
+                self.state().locals.push(local);
+                break;
+            }
+
+            if local.is_captured {
+                self.emit(OpCode::CloseUpvalue, span);
+            } else {
+                self.emit(OpCode::Pop, span);
+            }
+        }
+    }
+
+    fn add_local(&mut self, name: &str, span: Span) -> Result<()> {
+        if self.state().locals.len() == MAX_OPERAND {
+            return Err(Error::TooManyLocals { span });
+        }
+
+        self.state().locals.push(Local {
+            name: name.to_string(),
+            depth: None,
+            is_captured: false,
+        });
+
+        Ok(())
+    }
+
+    fn mark_initialized(&mut self) {
+        let state = self.state();
+        if state.scope_depth == 0 {
+            return;
+        }
+
+        let depth = state.scope_depth;
+        if let Some(local) = state.locals.last_mut() {
+            local.depth = Some(depth);
+        }
+    }
+
+    /// Globals are late bound and need no declaration, locals get a slot.
+    /// The resolver already rejected redeclarations in the same scope.
+    fn declare_variable(&mut self, name: &str, span: Span) -> Result<()> {
+        if self.state().scope_depth == 0 {
+            return Ok(());
+        }
+
+        self.add_local(name, span)
+    }
+
+    /// Returns the constant holding the name of a global, or zero for a
+    /// local, which is defined by its position on the stack instead.
+    fn parse_variable(&mut self, name: &str, span: Span) -> Result<u8> {
+        self.declare_variable(name, span)?;
+
+        if self.state().scope_depth > 0 {
+            return Ok(0);
+        }
+
+        self.identifier_constant(name, span)
+    }
+
+    fn define_variable(&mut self, global: u8, span: Span) {
+        if self.state().scope_depth > 0 {
+            self.mark_initialized();
+            return;
+        }
+
+        self.emit_with_operand(OpCode::DefineGlobal, global, span);
+    }
+
+    fn resolve_local(&self, function: usize, name: &str) -> Option<u8> {
+        self.functions[function]
+            .locals
+            .iter()
+            .rposition(|local| local.name == name)
+            .map(|slot| slot as u8)
+    }
+
+    fn resolve_upvalue(&mut self, function: usize, name: &str, span: Span) -> Result<Option<u8>> {
+        if function == 0 {
+            return Ok(None);
+        }
+
+        let enclosing = function - 1;
+
+        if let Some(slot) = self.resolve_local(enclosing, name) {
+            self.functions[enclosing].locals[slot as usize].is_captured = true;
+            return self.add_upvalue(function, slot, true, span).map(Some);
+        }
+
+        match self.resolve_upvalue(enclosing, name, span)? {
+            Some(index) => self.add_upvalue(function, index, false, span).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn add_upvalue(
+        &mut self,
+        function: usize,
+        index: u8,
+        is_local: bool,
+        span: Span,
+    ) -> Result<u8> {
+        let upvalue = UpvalueSlot { index, is_local };
+        let upvalues = &mut self.functions[function].upvalues;
+
+        if let Some(existing) = upvalues.iter().position(|u| *u == upvalue) {
+            return Ok(existing as u8);
+        }
+
+        if upvalues.len() == MAX_OPERAND {
+            return Err(Error::TooManyClosureVariables { span });
+        }
+        upvalues.push(upvalue);
+
+        Ok((upvalues.len() - 1) as u8)
+    }
+
+    /// Reads the variable `name`, or assigns `value` to it.
+    fn named_variable(&mut self, name: &str, span: Span, value: Option<&Expr>) -> Result<()> {
+        let current = self.functions.len() - 1;
+
+        let (get, set, operand) = if let Some(slot) = self.resolve_local(current, name) {
+            (OpCode::GetLocal, OpCode::SetLocal, slot)
+        } else if let Some(index) = self.resolve_upvalue(current, name, span)? {
+            (OpCode::GetUpvalue, OpCode::SetUpvalue, index)
+        } else {
+            let constant = self.identifier_constant(name, span)?;
+            (OpCode::GetGlobal, OpCode::SetGlobal, constant)
+        };
+
+        match value {
+            Some(value) => {
+                self.expression(value)?;
+                self.emit_with_operand(set, operand, span);
+            }
+            None => self.emit_with_operand(get, operand, span),
+        }
+
+        Ok(())
+    }
+
+    fn statement(&mut self, stmt: &Stmt) -> Result<()> {
+        match stmt {
+            Stmt::Expression(expr) => {
+                self.expression(expr)?;
+                self.emit(OpCode::Pop, expr.span());
+            }
+            Stmt::Print(expr) => {
+                self.expression(expr)?;
+                self.emit(OpCode::Print, expr.span());
+            }
+            Stmt::Var {
+                name,
+                initializer,
+                span,
+            } => {
+                let global = self.parse_variable(name, *span)?;
+
+                match initializer {
+                    Some(initializer) => self.expression(initializer)?,
+                    None => self.emit(OpCode::Nil, *span),
+                }
+
+                self.define_variable(global, *span);
+            }
+            Stmt::Block(statements) => {
+                self.begin_scope();
+                for stmt in statements {
+                    self.statement(stmt)?;
+                }
+                self.end_scope();
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let span = condition.span();
+                self.expression(condition)?;
+
+                let then_jump = self.emit_jump(OpCode::JumpIfFalse, span);
+                self.emit(OpCode::Pop, span);
+                self.statement(then_branch)?;
+
+                let else_jump = self.emit_jump(OpCode::Jump, span);
+                self.patch_jump(then_jump, span)?;
+                self.emit(OpCode::Pop, span);
+
+                if let Some(else_branch) = else_branch {
+                    self.statement(else_branch)?;
+                }
+                self.patch_jump(else_jump, span)?;
+            }
+            Stmt::While { condition, body } => {
+                let span = condition.span();
+                let loop_start = self.chunk().code.len();
+                self.expression(condition)?;
+
+                let exit_jump = self.emit_jump(OpCode::JumpIfFalse, span);
+                self.emit(OpCode::Pop, span);
+                self.statement(body)?;
+                self.emit_loop(loop_start, span)?;
+
+                self.patch_jump(exit_jump, span)?;
+                self.emit(OpCode::Pop, span);
+            }
+            Stmt::Function(declaration) => {
+                let global = self.parse_variable(&declaration.name, declaration.span)?;
+                // A function can refer to itself, for recursion.
+                self.mark_initialized();
+                self.function(declaration, FunctionKind::Function)?;
+                self.define_variable(global, declaration.span);
+            }
+            Stmt::Class(class) => self.class(class)?,
+            Stmt::Return { value, span } => match value {
+                Some(value) => {
+                    self.expression(value)?;
+                    self.emit(OpCode::Return, *span);
+                }
+                None => self.emit_return(*span),
+            },
+        }
+
+        Ok(())
+    }
+
+    fn function(&mut self, declaration: &FunctionDecl, kind: FunctionKind) -> Result<()> {
+        let span = declaration.span;
+
+        self.functions
+            .push(FunctionState::new(kind, Some(declaration.name.to_string())));
+        self.begin_scope();
+
+        for param in &declaration.params {
+            self.state().arity += 1;
+            self.add_local(&param.name, param.span)?;
+            self.mark_initialized();
+        }
+
+        for stmt in &declaration.body {
+            self.statement(stmt)?;
+        }
+
+        let (function, upvalues) = self.end_function();
+
+        let constant = self.make_constant(Value::Object(function), span)?;
+        self.emit_with_operand(OpCode::Closure, constant, span);
+        for upvalue in upvalues {
+            self.emit_byte(upvalue.is_local as u8, span);
+            self.emit_byte(upvalue.index, span);
+        }
+
+        Ok(())
+    }
+
+    fn class(&mut self, class: &ClassDecl) -> Result<()> {
+        let span = class.span;
+
+        let name = self.identifier_constant(&class.name, span)?;
+        self.declare_variable(&class.name, span)?;
+        self.emit_with_operand(OpCode::Class, name, span);
+        self.define_variable(name, span);
+
+        if let Some(superclass) = &class.superclass {
+            self.expression(superclass)?;
+
+            // `super` is a local of a scope wrapping the methods, which
+            // capture it like any other variable.
+            self.begin_scope();
+            self.add_local("super", span)?;
+            self.define_variable(0, span);
+
+            self.named_variable(&class.name, span, None)?;
+            self.emit(OpCode::Inherit, span);
+        }
+
+        self.named_variable(&class.name, span, None)?;
+
+        for method in &class.methods {
+            let name = self.identifier_constant(&method.name, method.span)?;
+            let kind = if method.name.as_str() == "init" {
+                FunctionKind::Initializer
+            } else {
+                FunctionKind::Method
+            };
+
+            self.function(method, kind)?;
+            self.emit_with_operand(OpCode::Method, name, method.span);
+        }
+
+        self.emit(OpCode::Pop, span);
+
+        if class.superclass.is_some() {
+            self.end_scope();
+        }
+
+        Ok(())
+    }
+
+    fn expression(&mut self, expr: &Expr) -> Result<()> {
+        match expr {
+            Expr::Literal { literal, span } => match literal {
+                Literal::Number(n) => self.emit_constant(Value::Number(*n), *span)?,
+                Literal::String(s) => {
+                    let string = self.heap.intern(s);
+                    self.emit_constant(Value::Object(string), *span)?;
+                }
+                Literal::True => self.emit(OpCode::True, *span),
+                Literal::False => self.emit(OpCode::False, *span),
+                Literal::Null => self.emit(OpCode::Nil, *span),
+            },
+            Expr::Grouping { expr, .. } => self.expression(expr)?,
+            Expr::Unary {
+                operator,
+                right,
+                operator_span,
+                ..
+            } => {
+                self.expression(right)?;
+
+                let op = match operator {
+                    UnaryOperator::Minus => OpCode::Negate,
+                    UnaryOperator::Bang => OpCode::Not,
+                };
+                self.emit(op, *operator_span);
+            }
+            Expr::Binary {
+                operator,
+                left,
+                right,
+                operator_span,
+                ..
+            } => {
+                self.expression(left)?;
+                self.expression(right)?;
+                self.binary(*operator, *operator_span);
+            }
+            Expr::Logical {
+                operator,
+                left,
+                right,
+                span,
+            } => {
+                self.expression(left)?;
+
+                match operator {
+                    LogicalOperator::And => {
+                        let end_jump = self.emit_jump(OpCode::JumpIfFalse, *span);
+                        self.emit(OpCode::Pop, *span);
+                        self.expression(right)?;
+                        self.patch_jump(end_jump, *span)?;
+                    }
+                    LogicalOperator::Or => {
+                        let else_jump = self.emit_jump(OpCode::JumpIfFalse, *span);
+                        let end_jump = self.emit_jump(OpCode::Jump, *span);
+                        self.patch_jump(else_jump, *span)?;
+                        self.emit(OpCode::Pop, *span);
+                        self.expression(right)?;
+                        self.patch_jump(end_jump, *span)?;
+                    }
+                }
+            }
+            Expr::Call {
+                callee,
+                arguments,
+                span,
+            } => self.call(callee, arguments, *span)?,
+            Expr::Interpolation { parts, span } => {
+                for part in parts {
+                    self.expression(part)?;
+                }
+
+                let count = u8::try_from(parts.len())
+                    .map_err(|_| Error::TooManyInterpolations { span: *span })?;
+                self.emit_with_operand(OpCode::Interpolate, count, *span);
+            }
+            Expr::Get {
+                object,
+                name,
+                name_span,
+                ..
+            } => {
+                self.expression(object)?;
+                let name = self.identifier_constant(name, *name_span)?;
+                self.emit_with_operand(OpCode::GetProperty, name, *name_span);
+            }
+            Expr::Set {
+                object,
+                name,
+                value,
+                name_span,
+                ..
+            } => {
+                self.expression(object)?;
+                self.expression(value)?;
+                let name = self.identifier_constant(name, *name_span)?;
+                self.emit_with_operand(OpCode::SetProperty, name, *name_span);
+            }
+            Expr::Variable { name, span, .. } => self.named_variable(name, *span, None)?,
+            Expr::Assign {
+                name, value, span, ..
+            } => self.named_variable(name, *span, Some(value))?,
+            Expr::This { span, .. } => self.named_variable("this", *span, None)?,
+            Expr::Super { method, span, .. } => {
+                self.named_variable("this", *span, None)?;
+                self.named_variable("super", *span, None)?;
+                let method = self.identifier_constant(method, *span)?;
+                self.emit_with_operand(OpCode::GetSuper, method, *span);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn binary(&mut self, operator: BinaryOperator, span: Span) {
+        // `!=`, `>=` and `<=` are the negation of another comparison.
+        let (op, negate) = match operator {
+            BinaryOperator::BangEqual => (OpCode::Equal, true),
+            BinaryOperator::EqualEqual => (OpCode::Equal, false),
+            BinaryOperator::Greater => (OpCode::Greater, false),
+            BinaryOperator::GreaterEqual => (OpCode::Less, true),
+            BinaryOperator::Less => (OpCode::Less, false),
+            BinaryOperator::LessEqual => (OpCode::Greater, true),
+            BinaryOperator::Minus => (OpCode::Subtract, false),
+            BinaryOperator::Plus => (OpCode::Add, false),
+            BinaryOperator::Division => (OpCode::Divide, false),
+            BinaryOperator::Multiplication => (OpCode::Multiply, false),
+        };
+
+        self.emit(op, span);
+        if negate {
+            self.emit(OpCode::Not, span);
+        }
+    }
+
+    /// Method calls are compiled to a single `Invoke` instead of looking the
+    /// method up, binding it and then calling the bound method.
+    fn call(&mut self, callee: &Expr, arguments: &[Expr], span: Span) -> Result<()> {
+        let argument_count = arguments.len() as u8;
+
+        match callee {
+            Expr::Get { object, name, .. } => {
+                self.expression(object)?;
+                self.arguments(arguments)?;
+                let name = self.identifier_constant(name, span)?;
+                self.emit_with_operand(OpCode::Invoke, name, span);
+                self.emit_byte(argument_count, span);
+            }
+            Expr::Super { method, .. } => {
+                self.named_variable("this", span, None)?;
+                self.arguments(arguments)?;
+                self.named_variable("super", span, None)?;
+                let method = self.identifier_constant(method, span)?;
+                self.emit_with_operand(OpCode::SuperInvoke, method, span);
+                self.emit_byte(argument_count, span);
+            }
+            _ => {
+                self.expression(callee)?;
+                self.arguments(arguments)?;
+                self.emit_with_operand(OpCode::Call, argument_count, span);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn arguments(&mut self, arguments: &[Expr]) -> Result<()> {
+        for argument in arguments {
+            self.expression(argument)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+use std::fmt::Display;
+
+use crate::{
+    diagnostics::{Diagnostic, ToDiagnostic},
+    primitives::Span,
+};
+
+pub mod bytecode_compiler;
+
+pub use bytecode_compiler::Compiler;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// Limits of the bytecode format that a valid program can still exceed.
+#[derive(Debug)]
+pub enum Error {
+    TooManyConstants { span: Span },
+    TooManyLocals { span: Span },
+    TooManyClosureVariables { span: Span },
+    JumpTooLarge { span: Span },
+    LoopTooLarge { span: Span },
+    TooManyInterpolations { span: Span },
+}
+
+impl Error {
+    fn parts(&self) -> (Span, &'static str) {
+        match self {
+            Error::TooManyConstants { span } => (*span, "Too many constants in one chunk."),
+            Error::TooManyLocals { span } => (*span, "Too many local variables in function."),
+            Error::TooManyClosureVariables { span } => {
+                (*span, "Too many closure variables in function.")
+            }
+            Error::JumpTooLarge { span } => (*span, "Too much code to jump over."),
+            Error::LoopTooLarge { span } => (*span, "Loop body too large."),
+            Error::TooManyInterpolations { span } => {
+                (*span, "Too many interpolated values in one string.")
+            }
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (span, message) = self.parts();
+        write!(f, "[line {}] Error: {message}", span.line)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ToDiagnostic for Error {
+    fn diagnostic(&self) -> Diagnostic {
+        let (span, message) = self.parts();
+        Diagnostic::error(message, span)
+    }
+}
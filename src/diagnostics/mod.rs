@@ -0,0 +1,108 @@
+use std::fmt::Display;
+
+use crate::primitives::Span;
+
+pub mod render;
+
+pub use render::Style;
+
+/// A message attached to a span that is not the main cause of an error,
+/// e.g. "left operand is a string here".
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+/// An error ready to be shown to the user together with the source it
+/// points into.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: Span,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            message: message.into(),
+            span,
+            labels: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn with_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+}
+
+/// Implemented by every error the interpreter can report. `Display` gives the
+/// one-line reference format, `diagnostic` the rich one.
+pub trait ToDiagnostic: Display {
+    fn diagnostic(&self) -> Diagnostic;
+}
+
+/// How errors are written to stderr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ErrorFormat {
+    /// `[line N] Error ...`, the format other Lox implementations use.
+    Short,
+    /// The offending source line with the span underlined.
+    Rich,
+}
+
+impl ErrorFormat {
+    /// Rich output is only the default when a person is reading stderr.
+    pub fn detect() -> Self {
+        if Style::detect() == Style::Color {
+            ErrorFormat::Rich
+        } else {
+            ErrorFormat::Short
+        }
+    }
+}
+
+/// Writes errors against a single source text.
+pub struct Reporter<'a> {
+    source: &'a str,
+    format: ErrorFormat,
+    style: Style,
+}
+
+impl<'a> Reporter<'a> {
+    pub fn new(source: &'a str, format: ErrorFormat) -> Self {
+        Self {
+            source,
+            format,
+            style: Style::detect(),
+        }
+    }
+
+    pub fn report(&self, error: &impl ToDiagnostic) {
+        match self.format {
+            ErrorFormat::Short => eprintln!("{error}"),
+            ErrorFormat::Rich => {
+                // A blank line keeps consecutive diagnostics apart.
+                eprintln!("{}", error.diagnostic().render(self.source, self.style))
+            }
+        }
+    }
+
+    pub fn report_all(&self, errors: &[impl ToDiagnostic]) {
+        for error in errors {
+            self.report(error);
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use std::{fmt::Write, io::IsTerminal};
+
+use crate::primitives::Span;
+
+use super::Diagnostic;
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// ANSI colours, for terminals.
+    Color,
+    /// No escape codes at all, for pipes and tests.
+    Plain,
+}
+
+impl Style {
+    pub fn detect() -> Self {
+        if std::io::stderr().is_terminal() {
+            Style::Color
+        } else {
+            Style::Plain
+        }
+    }
+
+    fn paint(self, colour: &'static str, text: &str) -> String {
+        match self {
+            Style::Color => format!("{colour}{text}{RESET}"),
+            Style::Plain => text.to_string(),
+        }
+    }
+}
+
+/// One underline drawn beneath a source line.
+struct Marker<'a> {
+    span: Span,
+    primary: bool,
+    message: &'a str,
+}
+
+/// The part of a source line a span covers, measured in characters.
+struct Underline<'s> {
+    text: &'s str,
+    offset: usize,
+    width: usize,
+}
+
+impl<'s> Underline<'s> {
+    /// `None` when the span does not point into `source`, as with the default
+    /// span of an error with no location.
+    fn locate(source: &'s str, span: Span) -> Option<Self> {
+        if span.line == 0
+            || span.end < span.start
+            || span.end > source.len()
+            || !source.is_char_boundary(span.start)
+            || !source.is_char_boundary(span.end)
+        {
+            return None;
+        }
+
+        let line_start = source[..span.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[span.start..]
+            .find('\n')
+            .map_or(source.len(), |i| span.start + i);
+
+        Some(Self {
+            text: source[line_start..line_end].trim_end_matches('\r'),
+            offset: source[line_start..span.start].chars().count(),
+            width: source[span.start..span.end.min(line_end)]
+                .chars()
+                .count()
+                .max(1),
+        })
+    }
+}
+
+impl Diagnostic {
+    /// Renders the diagnostic as
+    ///
+    /// ```text
+    /// error: Operands must be numbers.
+    ///  --> 1:11
+    ///   |
+    /// 1 | print "a" - 1;
+    ///   |           ^
+    ///   |       --- left operand is a string here
+    /// ```
+    pub fn render(&self, source: &str, style: Style) -> String {
+        let mut out = String::new();
+
+        let _ = writeln!(
+            out,
+            "{}{}",
+            style.paint(RED, "error"),
+            style.paint(BOLD, &format!(": {}", self.message))
+        );
+
+        let mut markers = vec![Marker {
+            span: self.span,
+            primary: true,
+            message: "",
+        }];
+        markers.extend(self.labels.iter().map(|label| Marker {
+            span: label.span,
+            primary: false,
+            message: &label.message,
+        }));
+
+        let located: Vec<_> = markers
+            .iter()
+            .filter_map(|marker| Some((marker, Underline::locate(source, marker.span)?)))
+            .collect();
+
+        let gutter = located
+            .iter()
+            .map(|(marker, _)| marker.span.line.to_string().len())
+            .max()
+            .unwrap_or(1);
+        let pipe = style.paint(BLUE, "|");
+
+        if self.span.line != 0 {
+            let _ = writeln!(
+                out,
+                "{:gutter$}{} {}:{}",
+                "",
+                style.paint(BLUE, "-->"),
+                self.span.line,
+                self.span.column
+            );
+        }
+
+        if !located.is_empty() {
+            let _ = writeln!(out, "{:gutter$} {pipe}", "");
+        }
+
+        let mut lines: Vec<usize> = located.iter().map(|(m, _)| m.span.line).collect();
+        lines.sort_unstable();
+        lines.dedup();
+
+        for line in lines {
+            let on_line: Vec<_> = located
+                .iter()
+                .filter(|(m, _)| m.span.line == line)
+                .collect();
+
+            let number = style.paint(BLUE, &format!("{line:>gutter$}"));
+            let _ = writeln!(out, "{number} {pipe} {}", on_line[0].1.text);
+
+            for (marker, underline) in on_line {
+                let (symbol, colour) = if marker.primary {
+                    ("^", RED)
+                } else {
+                    ("-", BLUE)
+                };
+                let mut drawn = symbol.repeat(underline.width);
+                if !marker.message.is_empty() {
+                    drawn.push(' ');
+                    drawn.push_str(marker.message);
+                }
+                let _ = writeln!(
+                    out,
+                    "{:gutter$} {pipe} {:offset$}{}",
+                    "",
+                    "",
+                    style.paint(colour, &drawn),
+                    offset = underline.offset
+                );
+            }
+        }
+
+        for note in &self.notes {
+            let _ = writeln!(
+                out,
+                "{:gutter$} {} note: {note}",
+                "",
+                style.paint(BLUE, "=")
+            );
+        }
+
+        out
+    }
+}
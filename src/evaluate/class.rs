@@ -0,0 +1,108 @@
+use std::{cell::RefCell, collections::HashMap, fmt::Display, rc::Rc};
+
+use crate::primitives::{Span, Symbol};
+
+use super::{Error, Function, Interpreter, Result, Value};
+
+pub struct Class {
+    pub name: Symbol,
+    pub superclass: Option<Rc<Class>>,
+    pub methods: HashMap<Symbol, Rc<Function>>,
+}
+
+impl Class {
+    pub fn new(
+        name: Symbol,
+        superclass: Option<Rc<Class>>,
+        methods: HashMap<Symbol, Rc<Function>>,
+    ) -> Self {
+        Self {
+            name,
+            superclass,
+            methods,
+        }
+    }
+
+    /// Looks the method up in this class first and then up the superclass chain.
+    pub fn find_method(&self, name: &Symbol) -> Option<Rc<Function>> {
+        match self.methods.get(name) {
+            Some(method) => Some(Rc::clone(method)),
+            None => self.superclass.as_ref()?.find_method(name),
+        }
+    }
+
+    /// A class takes as many arguments as its `init` method, if it has one.
+    /// `init` is the interned method name.
+    pub fn arity(&self, init: &Symbol) -> usize {
+        self.find_method(init).map_or(0, |init| init.arity())
+    }
+
+    pub fn instantiate(
+        class: &Rc<Class>,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Value>,
+    ) -> Result<Value> {
+        let instance = Rc::new(RefCell::new(Instance::new(Rc::clone(class))));
+
+        if let Some(init) = class.find_method(&interpreter.init_symbol) {
+            init.bind(Rc::clone(&instance), &interpreter.this_symbol)
+                .call(interpreter, arguments)?;
+        }
+
+        Ok(Value::Instance(instance))
+    }
+}
+
+impl Display for Class {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<class {}>", self.name)
+    }
+}
+
+pub struct Instance {
+    pub class: Rc<Class>,
+    fields: HashMap<Symbol, Value>,
+}
+
+impl Instance {
+    pub fn new(class: Rc<Class>) -> Self {
+        Self {
+            class,
+            fields: HashMap::new(),
+        }
+    }
+
+    /// Fields shadow methods; methods are bound to the instance they are
+    /// read from, as `this`.
+    pub fn get(
+        instance: &Rc<RefCell<Instance>>,
+        name: &Symbol,
+        span: Span,
+        this: &Symbol,
+    ) -> Result<Value> {
+        if let Some(value) = instance.borrow().fields.get(name) {
+            return Ok(value.clone());
+        }
+
+        let method = instance.borrow().class.find_method(name);
+        match method {
+            Some(method) => Ok(Value::Function(Rc::new(
+                method.bind(Rc::clone(instance), this),
+            ))),
+            None => Err(Error::UndefinedProperty {
+                name: name.to_string(),
+                span,
+            }),
+        }
+    }
+
+    pub fn set(&mut self, name: &Symbol, value: Value) {
+        self.fields.insert(name.clone(), value);
+    }
+}
+
+impl Display for Instance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} instance", self.class.name)
+    }
+}
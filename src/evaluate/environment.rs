@@ -0,0 +1,76 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::primitives::{Span, Symbol};
+
+use super::{Error, Result, Value};
+
+#[derive(Default)]
+pub struct Environment {
+    values: HashMap<Symbol, Value>,
+    enclosing: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_enclosing(enclosing: Rc<RefCell<Environment>>) -> Self {
+        Self {
+            values: HashMap::new(),
+            enclosing: Some(enclosing),
+        }
+    }
+
+    pub fn define(&mut self, name: &Symbol, value: Value) {
+        self.values.insert(name.clone(), value);
+    }
+
+    /// Walks `distance` enclosing environments up from `environment`.
+    pub fn ancestor(
+        environment: &Rc<RefCell<Environment>>,
+        distance: usize,
+    ) -> Rc<RefCell<Environment>> {
+        let mut environment = Rc::clone(environment);
+
+        for _ in 0..distance {
+            let enclosing = environment
+                .borrow()
+                .enclosing
+                .clone()
+                .expect("The resolver only produces distances to existing scopes");
+            environment = enclosing;
+        }
+
+        environment
+    }
+
+    pub fn get(&self, name: &Symbol, span: Span) -> Result<Value> {
+        if let Some(value) = self.values.get(name) {
+            return Ok(value.clone());
+        }
+
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow().get(name, span),
+            None => Err(Error::UndefinedVariable {
+                name: name.to_string(),
+                span,
+            }),
+        }
+    }
+
+    pub fn assign(&mut self, name: &Symbol, value: Value, span: Span) -> Result<()> {
+        if let Some(slot) = self.values.get_mut(name) {
+            *slot = value;
+            return Ok(());
+        }
+
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow_mut().assign(name, value, span),
+            None => Err(Error::UndefinedVariable {
+                name: name.to_string(),
+                span,
+            }),
+        }
+    }
+}
@@ -1,76 +1,309 @@
-use crate::expr::{binary::BinaryOperator, expression::Expr, unary::UnaryOperator};
+use crate::{
+    expr::{
+        binary::BinaryOperator, expression::Expr, logical::LogicalOperator, unary::UnaryOperator,
+    },
+    primitives::{Span, Symbol},
+};
 
-use super::{Error, Result, Value};
+use std::{fmt::Write, rc::Rc};
 
-pub fn eval(expr: Expr) -> Result<Value> {
-    match expr {
-        Expr::Literal { literal, line: _ } => Ok(literal.into()),
-        Expr::Grouping(e) => Ok(eval(*e)?),
-        Expr::Unary { operator, right } => eval_unary(operator, *right),
-        Expr::Binary {
-            operator,
-            left,
-            right,
-        } => eval_binary(operator, *left, *right),
+use super::{
+    interpreter::{CallFrame, FRAMES_MAX},
+    native, Class, Environment, Error, Instance, Interpreter, Operand, Result, Value,
+};
+
+impl Interpreter {
+    pub fn eval(&mut self, expr: &Expr) -> Result<Value> {
+        match expr {
+            Expr::Literal { literal, .. } => Ok(literal.clone().into()),
+            Expr::Grouping { expr, .. } => self.eval(expr),
+            Expr::Interpolation { parts, .. } => {
+                let mut string = String::new();
+                for part in parts {
+                    let _ = write!(string, "{}", self.eval(part)?);
+                }
+
+                Ok(Value::String(Symbol::intern(&string)))
+            }
+            Expr::Get {
+                object,
+                name,
+                name_span,
+                ..
+            } => match self.eval(object)? {
+                Value::Instance(instance) => {
+                    Instance::get(&instance, name, *name_span, &self.this_symbol)
+                }
+                _ => Err(Error::NotAnInstance { span: *name_span }),
+            },
+            Expr::Set {
+                object,
+                name,
+                value,
+                name_span,
+                ..
+            } => {
+                let Value::Instance(instance) = self.eval(object)? else {
+                    return Err(Error::NotAnInstanceField { span: *name_span });
+                };
+
+                let value = self.eval(value)?;
+                instance.borrow_mut().set(name, value.clone());
+
+                Ok(value)
+            }
+            Expr::Call {
+                callee,
+                arguments,
+                span,
+            } => self.eval_call(callee, arguments, *span),
+            Expr::Unary {
+                operator,
+                right,
+                operator_span,
+                ..
+            } => self.eval_unary(*operator, *operator_span, right),
+            Expr::Binary {
+                operator,
+                left,
+                right,
+                operator_span,
+                ..
+            } => self.eval_binary(*operator, *operator_span, left, right),
+            Expr::Logical {
+                operator,
+                left,
+                right,
+                ..
+            } => self.eval_logical(*operator, left, right),
+            Expr::Variable { name, span, depth } => self.look_up(name, *span, depth.get()),
+            Expr::This { span, depth } => self.look_up(&self.this_symbol, *span, depth.get()),
+            Expr::Super {
+                method,
+                span,
+                depth,
+            } => self.eval_super(method, *span, depth.get()),
+            Expr::Assign {
+                name,
+                value,
+                span,
+                depth,
+            } => {
+                let value = self.eval(value)?;
+
+                let environment = match depth.get() {
+                    Some(distance) => Environment::ancestor(&self.environment, distance),
+                    None => Rc::clone(&self.globals),
+                };
+                environment
+                    .borrow_mut()
+                    .assign(name, value.clone(), *span)?;
+
+                Ok(value)
+            }
+        }
     }
-}
 
-fn eval_unary(operator: UnaryOperator, right: Expr) -> Result<Value> {
-    let value = eval(right)?;
-    match operator {
-        UnaryOperator::Bang => match value {
-            Value::Bool(b) => Ok(Value::Bool(!b)),
-            Value::Nil => Ok(Value::Bool(true)),
-            _ => Ok(Value::Bool(false)),
-        },
-        UnaryOperator::Minus => match value {
-            Value::Number(n) => Ok(Value::Number(-n)),
-            _ => Err(Error::InvalidUnaryOperand),
-        },
+    fn eval_unary(
+        &mut self,
+        operator: UnaryOperator,
+        operator_span: Span,
+        right: &Expr,
+    ) -> Result<Value> {
+        let value = self.eval(right)?;
+        match operator {
+            UnaryOperator::Bang => Ok(Value::Bool(!value.is_truthy())),
+            UnaryOperator::Minus => match value {
+                Value::Number(n) => Ok(Value::Number(-n)),
+                _ => Err(Error::InvalidUnaryOperand {
+                    span: operator_span,
+                    operand: Operand::new(right.span(), &value),
+                }),
+            },
+        }
     }
-}
 
-fn eval_binary(operator: BinaryOperator, left: Expr, right: Expr) -> Result<Value> {
-    let left = eval(left)?;
-    let right = eval(right)?;
-
-    match operator {
-        BinaryOperator::Division => match (left, right) {
-            (Value::Number(left), Value::Number(right)) => Ok(Value::Number(left / right)),
-            _ => Err(Error::InvalidBinaryOperand { operator }),
-        },
-        BinaryOperator::Multiplication => match (left, right) {
-            (Value::Number(left), Value::Number(right)) => Ok(Value::Number(left * right)),
-            _ => Err(Error::InvalidBinaryOperand { operator }),
-        },
-        BinaryOperator::Minus => match (left, right) {
-            (Value::Number(left), Value::Number(right)) => Ok(Value::Number(left - right)),
-            _ => Err(Error::InvalidBinaryOperand { operator }),
-        },
-        BinaryOperator::Plus => match (left, right) {
-            (Value::Number(left), Value::Number(right)) => Ok(Value::Number(left + right)),
-            (Value::String(left), Value::String(right)) => {
-                Ok(Value::String(format!("{left}{right}")))
+    fn look_up(&self, name: &Symbol, span: Span, depth: Option<usize>) -> Result<Value> {
+        match depth {
+            Some(distance) => Environment::ancestor(&self.environment, distance)
+                .borrow()
+                .get(name, span),
+            None => self.globals.borrow().get(name, span),
+        }
+    }
+
+    fn eval_super(&self, method: &Symbol, span: Span, depth: Option<usize>) -> Result<Value> {
+        let distance = depth.expect("The resolver always binds 'super' to a local scope");
+
+        let Value::Class(superclass) = Environment::ancestor(&self.environment, distance)
+            .borrow()
+            .get(&self.super_symbol, span)?
+        else {
+            unreachable!("'super' is only ever bound to a class");
+        };
+
+        // `this` is always bound in the scope right inside the one defining `super`.
+        let Value::Instance(instance) = Environment::ancestor(&self.environment, distance - 1)
+            .borrow()
+            .get(&self.this_symbol, span)?
+        else {
+            unreachable!("'this' is only ever bound to an instance");
+        };
+
+        match superclass.find_method(method) {
+            Some(method) => Ok(Value::Function(Rc::new(
+                method.bind(instance, &self.this_symbol),
+            ))),
+            None => Err(Error::UndefinedProperty {
+                name: method.to_string(),
+                span,
+            }),
+        }
+    }
+
+    fn eval_call(&mut self, callee: &Expr, arguments: &[Expr], span: Span) -> Result<Value> {
+        let callee = self.eval(callee)?;
+
+        let arguments = arguments
+            .iter()
+            .map(|argument| self.eval(argument))
+            .collect::<Result<Vec<_>>>()?;
+
+        let arity = match &callee {
+            Value::Function(function) => function.arity(),
+            Value::NativeFunction(function) => function.arity,
+            Value::Class(class) => class.arity(&self.init_symbol),
+            _ => return Err(Error::NotCallable { span }),
+        };
+
+        if arguments.len() != arity {
+            return Err(Error::ArityMismatch {
+                expected: arity,
+                found: arguments.len(),
+                span,
+            });
+        }
+
+        let function = match &callee {
+            Value::Function(function) => Some(Rc::clone(&function.declaration)),
+            Value::Class(class) => class
+                .find_method(&self.init_symbol)
+                .map(|init| Rc::clone(&init.declaration)),
+            _ => None,
+        };
+
+        // Native functions get no frame, they can only fail at the call site.
+        let Some(function) = function else {
+            return self.call_value(callee, arguments, span);
+        };
+
+        // The script itself takes the first of the VM's frames.
+        if self.frames.len() + 1 == FRAMES_MAX {
+            return Err(Error::StackOverflow { span });
+        }
+
+        self.frames.push(CallFrame {
+            function,
+            call_site: span,
+        });
+        let result = self.call_value(callee, arguments, span);
+        if let Err(error) = &result {
+            self.capture_trace(error);
+        }
+        self.frames.pop();
+
+        result
+    }
+
+    fn call_value(&mut self, callee: Value, arguments: Vec<Value>, span: Span) -> Result<Value> {
+        match callee {
+            Value::Function(function) => function.call(self, arguments),
+            Value::NativeFunction(function) => {
+                native::call(&function, &arguments).map_err(|message| Error::NativeFailure {
+                    name: function.name.clone(),
+                    message,
+                    span,
+                })
             }
-            _ => Err(Error::InvalidBinaryOperand { operator }),
-        },
-        BinaryOperator::Greater => match (left, right) {
-            (Value::Number(left), Value::Number(right)) => Ok(Value::Bool(left > right)),
-            _ => Err(Error::InvalidBinaryOperand { operator }),
-        },
-        BinaryOperator::GreaterEqual => match (left, right) {
-            (Value::Number(left), Value::Number(right)) => Ok(Value::Bool(left >= right)),
-            _ => Err(Error::InvalidBinaryOperand { operator }),
-        },
-        BinaryOperator::Less => match (left, right) {
-            (Value::Number(left), Value::Number(right)) => Ok(Value::Bool(left < right)),
-            _ => Err(Error::InvalidBinaryOperand { operator }),
-        },
-        BinaryOperator::LessEqual => match (left, right) {
-            (Value::Number(left), Value::Number(right)) => Ok(Value::Bool(left <= right)),
-            _ => Err(Error::InvalidBinaryOperand { operator }),
-        },
-        BinaryOperator::BangEqual => Ok(Value::Bool(left != right)),
-        BinaryOperator::EqualEqual => Ok(Value::Bool(left == right)),
+            Value::Class(class) => Class::instantiate(&class, self, arguments),
+            _ => unreachable!("Only callable values have an arity"),
+        }
+    }
+
+    fn eval_logical(
+        &mut self,
+        operator: LogicalOperator,
+        left: &Expr,
+        right: &Expr,
+    ) -> Result<Value> {
+        let left = self.eval(left)?;
+
+        match operator {
+            LogicalOperator::Or if left.is_truthy() => Ok(left),
+            LogicalOperator::And if !left.is_truthy() => Ok(left),
+            _ => self.eval(right),
+        }
+    }
+
+    fn eval_binary(
+        &mut self,
+        operator: BinaryOperator,
+        operator_span: Span,
+        left: &Expr,
+        right: &Expr,
+    ) -> Result<Value> {
+        let left_operand = self.eval(left)?;
+        let right_operand = self.eval(right)?;
+        let operands = (
+            Operand::new(left.span(), &left_operand),
+            Operand::new(right.span(), &right_operand),
+        );
+        let invalid = move || Error::InvalidBinaryOperand {
+            operator,
+            span: operator_span,
+            left: Box::new(operands.0),
+            right: Box::new(operands.1),
+        };
+        let (left, right) = (left_operand, right_operand);
+
+        match operator {
+            BinaryOperator::Division => match (left, right) {
+                (Value::Number(left), Value::Number(right)) => Ok(Value::Number(left / right)),
+                _ => Err(invalid()),
+            },
+            BinaryOperator::Multiplication => match (left, right) {
+                (Value::Number(left), Value::Number(right)) => Ok(Value::Number(left * right)),
+                _ => Err(invalid()),
+            },
+            BinaryOperator::Minus => match (left, right) {
+                (Value::Number(left), Value::Number(right)) => Ok(Value::Number(left - right)),
+                _ => Err(invalid()),
+            },
+            BinaryOperator::Plus => match (left, right) {
+                (Value::Number(left), Value::Number(right)) => Ok(Value::Number(left + right)),
+                (Value::String(left), Value::String(right)) => {
+                    Ok(Value::String(Symbol::intern(&format!("{left}{right}"))))
+                }
+                _ => Err(invalid()),
+            },
+            BinaryOperator::Greater => match (left, right) {
+                (Value::Number(left), Value::Number(right)) => Ok(Value::Bool(left > right)),
+                _ => Err(invalid()),
+            },
+            BinaryOperator::GreaterEqual => match (left, right) {
+                (Value::Number(left), Value::Number(right)) => Ok(Value::Bool(left >= right)),
+                _ => Err(invalid()),
+            },
+            BinaryOperator::Less => match (left, right) {
+                (Value::Number(left), Value::Number(right)) => Ok(Value::Bool(left < right)),
+                _ => Err(invalid()),
+            },
+            BinaryOperator::LessEqual => match (left, right) {
+                (Value::Number(left), Value::Number(right)) => Ok(Value::Bool(left <= right)),
+                _ => Err(invalid()),
+            },
+            BinaryOperator::BangEqual => Ok(Value::Bool(left != right)),
+            BinaryOperator::EqualEqual => Ok(Value::Bool(left == right)),
+        }
     }
 }
@@ -0,0 +1,133 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::expr::{expression::Expr, statement::Stmt};
+
+use super::{Class, Environment, Error, Function, Interpreter, Result, Value};
+
+impl Interpreter {
+    /// Executes a single statement. `Some(value)` means a `return` statement
+    /// was reached and the enclosing function call has to unwind with it.
+    pub fn execute(&mut self, stmt: &Stmt) -> Result<Option<Value>> {
+        match stmt {
+            Stmt::Expression(expr) => {
+                self.eval(expr)?;
+            }
+            Stmt::Print(expr) => println!("{}", self.eval(expr)?),
+            Stmt::Var {
+                name, initializer, ..
+            } => {
+                let value = match initializer {
+                    Some(expr) => self.eval(expr)?,
+                    None => Value::Nil,
+                };
+
+                self.environment.borrow_mut().define(name, value);
+            }
+            Stmt::Block(statements) => {
+                let environment = Environment::with_enclosing(Rc::clone(&self.environment));
+                return self.execute_block(statements, Rc::new(RefCell::new(environment)));
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                if self.eval(condition)?.is_truthy() {
+                    return self.execute(then_branch);
+                } else if let Some(else_branch) = else_branch {
+                    return self.execute(else_branch);
+                }
+            }
+            Stmt::While { condition, body } => {
+                while self.eval(condition)?.is_truthy() {
+                    if let Some(value) = self.execute(body)? {
+                        return Ok(Some(value));
+                    }
+                }
+            }
+            Stmt::Function(declaration) => {
+                let function =
+                    Function::new(Rc::clone(declaration), Rc::clone(&self.environment), false);
+
+                self.environment
+                    .borrow_mut()
+                    .define(&declaration.name, Value::Function(Rc::new(function)));
+            }
+            Stmt::Class(declaration) => {
+                let superclass = match &declaration.superclass {
+                    Some(expr) => match self.eval(expr)? {
+                        Value::Class(class) => Some(class),
+                        _ => {
+                            let Expr::Variable { span, .. } = expr else {
+                                unreachable!("The parser only produces variables as superclasses")
+                            };
+
+                            return Err(Error::SuperclassNotAClass { span: *span });
+                        }
+                    },
+                    None => None,
+                };
+
+                // Methods of a subclass close over an environment defining `super`.
+                let enclosing = Rc::clone(&self.environment);
+                if let Some(superclass) = &superclass {
+                    let mut environment = Environment::with_enclosing(Rc::clone(&enclosing));
+                    environment.define(&self.super_symbol, Value::Class(Rc::clone(superclass)));
+                    self.environment = Rc::new(RefCell::new(environment));
+                }
+
+                let methods = declaration
+                    .methods
+                    .iter()
+                    .map(|method| {
+                        let function = Function::new(
+                            Rc::clone(method),
+                            Rc::clone(&self.environment),
+                            method.name == self.init_symbol,
+                        );
+
+                        (method.name.clone(), Rc::new(function))
+                    })
+                    .collect::<HashMap<_, _>>();
+
+                self.environment = enclosing;
+
+                let class = Class::new(declaration.name.clone(), superclass, methods);
+                self.environment
+                    .borrow_mut()
+                    .define(&declaration.name, Value::Class(Rc::new(class)));
+            }
+            Stmt::Return { value, span: _ } => {
+                let value = match value {
+                    Some(expr) => self.eval(expr)?,
+                    None => Value::Nil,
+                };
+
+                return Ok(Some(value));
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub(super) fn execute_block(
+        &mut self,
+        statements: &[Stmt],
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<Option<Value>> {
+        let previous = std::mem::replace(&mut self.environment, environment);
+
+        // The previous environment has to be restored even if a statement fails.
+        let mut result = Ok(None);
+        for stmt in statements {
+            result = self.execute(stmt);
+
+            if !matches!(result, Ok(None)) {
+                break;
+            }
+        }
+        self.environment = previous;
+
+        result
+    }
+}
@@ -0,0 +1,70 @@
+use std::{cell::RefCell, fmt::Display, rc::Rc};
+
+use crate::{expr::statement::FunctionDecl, primitives::Symbol};
+
+use super::{Environment, Instance, Interpreter, Result, Value};
+
+pub struct Function {
+    pub declaration: Rc<FunctionDecl>,
+    /// The environment the function was declared in, kept alive for as long
+    /// as the function itself so its body can keep reading and assigning it.
+    pub closure: Rc<RefCell<Environment>>,
+    /// Initializers always return `this`, even on an early `return;`.
+    pub is_initializer: bool,
+}
+
+impl Function {
+    pub fn new(
+        declaration: Rc<FunctionDecl>,
+        closure: Rc<RefCell<Environment>>,
+        is_initializer: bool,
+    ) -> Self {
+        Self {
+            declaration,
+            closure,
+            is_initializer,
+        }
+    }
+
+    /// Creates a copy of the method whose closure defines `this` as `instance`.
+    /// `this` is the interned name to define.
+    pub fn bind(&self, instance: Rc<RefCell<Instance>>, this: &Symbol) -> Function {
+        let mut environment = Environment::with_enclosing(Rc::clone(&self.closure));
+        environment.define(this, Value::Instance(instance));
+
+        Function::new(
+            Rc::clone(&self.declaration),
+            Rc::new(RefCell::new(environment)),
+            self.is_initializer,
+        )
+    }
+
+    pub fn arity(&self) -> usize {
+        self.declaration.params.len()
+    }
+
+    pub fn call(&self, interpreter: &mut Interpreter, arguments: Vec<Value>) -> Result<Value> {
+        let mut environment = Environment::with_enclosing(Rc::clone(&self.closure));
+        for (param, argument) in self.declaration.params.iter().zip(arguments) {
+            environment.define(&param.name, argument);
+        }
+
+        let returned = interpreter
+            .execute_block(&self.declaration.body, Rc::new(RefCell::new(environment)))?;
+
+        if self.is_initializer {
+            return self
+                .closure
+                .borrow()
+                .get(&interpreter.this_symbol, self.declaration.span);
+        }
+
+        Ok(returned.unwrap_or(Value::Nil))
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<fn {}>", self.declaration.name)
+    }
+}
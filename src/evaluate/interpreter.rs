@@ -0,0 +1,117 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    expr::{
+        expression::Expr,
+        statement::{FunctionDecl, Stmt},
+    },
+    native::{self, Native},
+    primitives::{Span, Symbol},
+};
+
+use super::{Environment, Error, RuntimeError, TraceLine, Value};
+
+/// Calls nested deeper than this are reported as a stack overflow, the same
+/// limit the VM has.
+pub(super) const FRAMES_MAX: usize = 1024;
+
+/// A call to a Lox function that has not returned yet.
+pub(super) struct CallFrame {
+    pub(super) function: Rc<FunctionDecl>,
+    pub(super) call_site: Span,
+}
+
+pub struct Interpreter {
+    pub(super) globals: Rc<RefCell<Environment>>,
+    pub(super) environment: Rc<RefCell<Environment>>,
+    pub(super) frames: Vec<CallFrame>,
+    /// Captured by the innermost call an error unwinds through, while its
+    /// frame is still on the stack.
+    trace: Option<Vec<TraceLine>>,
+    /// The interned `"this"`, `"init"` and `"super"`, looked up on every
+    /// method access, call and instantiation.
+    pub(super) this_symbol: Symbol,
+    pub(super) init_symbol: Symbol,
+    pub(super) super_symbol: Symbol,
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        let globals = Rc::new(RefCell::new(Environment::new()));
+
+        let mut interpreter = Self {
+            environment: Rc::clone(&globals),
+            globals,
+            frames: Vec::new(),
+            trace: None,
+            this_symbol: Symbol::intern("this"),
+            init_symbol: Symbol::intern("init"),
+            super_symbol: Symbol::intern("super"),
+        };
+
+        for native in native::standard() {
+            interpreter.define_native(native);
+        }
+
+        interpreter
+    }
+
+    /// Installs a Rust function as a global that scripts can call like any
+    /// other Lox function.
+    pub fn define_native(&mut self, native: Native) {
+        self.globals.borrow_mut().define(
+            &Symbol::intern(&native.name),
+            Value::NativeFunction(Rc::new(native)),
+        );
+    }
+
+    pub fn run(&mut self, program: &[Stmt]) -> Result<(), RuntimeError> {
+        for stmt in program {
+            match self.execute(stmt) {
+                Ok(Some(_)) => break,
+                Ok(None) => {}
+                Err(error) => return Err(self.runtime_error(error)),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Evaluates a single expression, e.g. for the `evaluate` command.
+    pub fn evaluate(&mut self, expr: &Expr) -> Result<Value, RuntimeError> {
+        self.eval(expr).map_err(|error| self.runtime_error(error))
+    }
+
+    /// Records the call stack for `error` unless a deeper call already did.
+    pub(super) fn capture_trace(&mut self, error: &Error) {
+        if self.trace.is_some() {
+            return;
+        }
+
+        let mut trace = Vec::with_capacity(self.frames.len() + 1);
+        let mut line = error.span().line;
+        for frame in self.frames.iter().rev() {
+            trace.push(TraceLine {
+                line,
+                function: Some(frame.function.name.to_string()),
+            });
+            line = frame.call_site.line;
+        }
+        trace.push(TraceLine {
+            line,
+            function: None,
+        });
+
+        self.trace = Some(trace);
+    }
+
+    fn runtime_error(&mut self, error: Error) -> RuntimeError {
+        self.capture_trace(&error);
+        self.frames.clear();
+
+        RuntimeError {
+            trace: self.trace.take().unwrap_or_default(),
+            error,
+        }
+    }
+}
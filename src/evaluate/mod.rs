@@ -1,24 +1,229 @@
+mod class;
+mod environment;
 mod eval;
+mod exec;
+mod function;
+mod interpreter;
+mod native;
 mod value;
 
-pub use eval::eval;
+pub use class::{Class, Instance};
+pub use environment::Environment;
+pub use function::Function;
+pub use interpreter::Interpreter;
 pub use value::Value;
 
-use crate::expr::binary::BinaryOperator;
+use crate::{
+    diagnostics::{Diagnostic, ToDiagnostic},
+    expr::binary::BinaryOperator,
+    primitives::Span,
+};
 
 pub type Result<T> = core::result::Result<T, Error>;
+
+/// Where an operand of a failed operation came from and what it evaluated to.
+#[derive(Clone, Copy)]
+pub struct Operand {
+    pub span: Span,
+    pub kind: &'static str,
+}
+
+impl Operand {
+    pub fn new(span: Span, value: &Value) -> Self {
+        Self {
+            span,
+            kind: value.type_name(),
+        }
+    }
+}
+
 pub enum Error {
-    InvalidUnaryOperand,
-    InvalidBinaryOperand { operator: BinaryOperator },
+    InvalidUnaryOperand {
+        span: Span,
+        operand: Operand,
+    },
+    InvalidBinaryOperand {
+        operator: BinaryOperator,
+        span: Span,
+        left: Box<Operand>,
+        right: Box<Operand>,
+    },
+    UndefinedVariable {
+        name: String,
+        span: Span,
+    },
+    NotCallable {
+        span: Span,
+    },
+    NotAnInstance {
+        span: Span,
+    },
+    SuperclassNotAClass {
+        span: Span,
+    },
+    NotAnInstanceField {
+        span: Span,
+    },
+    UndefinedProperty {
+        name: String,
+        span: Span,
+    },
+    NativeFailure {
+        name: String,
+        message: String,
+        span: Span,
+    },
+    ArityMismatch {
+        expected: usize,
+        found: usize,
+        span: Span,
+    },
+    StackOverflow {
+        span: Span,
+    },
+}
+
+impl Error {
+    pub fn span(&self) -> Span {
+        match self {
+            Error::InvalidUnaryOperand { span, .. }
+            | Error::InvalidBinaryOperand { span, .. }
+            | Error::UndefinedVariable { span, .. }
+            | Error::NotCallable { span }
+            | Error::NotAnInstance { span }
+            | Error::SuperclassNotAClass { span }
+            | Error::NotAnInstanceField { span }
+            | Error::UndefinedProperty { span, .. }
+            | Error::NativeFailure { span, .. }
+            | Error::ArityMismatch { span, .. }
+            | Error::StackOverflow { span } => *span,
+        }
+    }
 }
 
+/// Only the message, the location is part of the trace of a `RuntimeError`.
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Error::InvalidUnaryOperand => write!(f, "Operand must be Numeric."),
-            Error::InvalidBinaryOperand { operator } => {
-                write!(f, "Operator '{operator}' used with invalid data types.")
+            Error::InvalidUnaryOperand { .. } => write!(f, "Operand must be a number."),
+            Error::InvalidBinaryOperand {
+                operator: BinaryOperator::Plus,
+                ..
+            } => write!(f, "Operands must be two numbers or two strings."),
+            Error::InvalidBinaryOperand { .. } => write!(f, "Operands must be numbers."),
+            Error::UndefinedVariable { name, .. } => write!(f, "Undefined variable '{name}'."),
+            Error::NotCallable { .. } => write!(f, "Can only call functions and classes."),
+            Error::NotAnInstance { .. } => write!(f, "Only instances have properties."),
+            Error::SuperclassNotAClass { .. } => write!(f, "Superclass must be a class."),
+            Error::NotAnInstanceField { .. } => write!(f, "Only instances have fields."),
+            Error::UndefinedProperty { name, .. } => write!(f, "Undefined property '{name}'."),
+            Error::NativeFailure { name, message, .. } => write!(f, "{name}: {message}"),
+            Error::ArityMismatch {
+                expected, found, ..
+            } => write!(f, "Expected {expected} arguments but got {found}."),
+            Error::StackOverflow { .. } => write!(f, "Stack overflow."),
+        }
+    }
+}
+
+/// Where execution was in one of the calls active when an error happened.
+/// `function` is `None` for the top level of the script.
+pub struct TraceLine {
+    pub line: usize,
+    pub function: Option<String>,
+}
+
+impl std::fmt::Display for TraceLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.function {
+            Some(name) => write!(f, "[line {}] in {name}()", self.line),
+            None => write!(f, "[line {}] in script", self.line),
+        }
+    }
+}
+
+/// An error that stopped the interpreter, with the calls that led to it,
+/// innermost first.
+pub struct RuntimeError {
+    pub error: Error,
+    pub trace: Vec<TraceLine>,
+}
+
+impl std::fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.error)?;
+        for line in &self.trace {
+            write!(f, "\n{line}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// "an instance", "a number", ...
+fn with_article(kind: &str) -> String {
+    match kind.chars().next() {
+        Some('a' | 'e' | 'i' | 'o' | 'u') => format!("an {kind}"),
+        _ => format!("a {kind}"),
+    }
+}
+
+impl ToDiagnostic for Error {
+    fn diagnostic(&self) -> Diagnostic {
+        let message = self.to_string();
+
+        match self {
+            Error::InvalidUnaryOperand { span, operand } => Diagnostic::error(message, *span)
+                .with_label(
+                    operand.span,
+                    format!("operand is {} here", with_article(operand.kind)),
+                ),
+            Error::InvalidBinaryOperand {
+                operator,
+                span,
+                left,
+                right,
+            } => {
+                let diagnostic = Diagnostic::error(message, *span)
+                    .with_label(
+                        left.span,
+                        format!("left operand is {} here", with_article(left.kind)),
+                    )
+                    .with_label(
+                        right.span,
+                        format!("right operand is {} here", with_article(right.kind)),
+                    );
+
+                if *operator == BinaryOperator::Plus {
+                    diagnostic
+                } else {
+                    diagnostic.with_note(format!("'{operator}' only works on numbers"))
+                }
             }
+            Error::UndefinedVariable { span, .. }
+            | Error::NotCallable { span }
+            | Error::NotAnInstance { span }
+            | Error::SuperclassNotAClass { span }
+            | Error::NotAnInstanceField { span }
+            | Error::UndefinedProperty { span, .. }
+            | Error::NativeFailure { span, .. }
+            | Error::ArityMismatch { span, .. }
+            | Error::StackOverflow { span } => Diagnostic::error(message, *span),
+        }
+    }
+}
+
+impl ToDiagnostic for RuntimeError {
+    fn diagnostic(&self) -> Diagnostic {
+        let diagnostic = self.error.diagnostic();
+
+        // A trace made of the script alone says nothing the snippet doesn't.
+        if self.trace.len() < 2 {
+            return diagnostic;
         }
+
+        self.trace.iter().fold(diagnostic, |diagnostic, line| {
+            diagnostic.with_note(line.to_string())
+        })
     }
 }
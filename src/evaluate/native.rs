@@ -0,0 +1,30 @@
+use std::rc::Rc;
+
+use crate::{
+    native::{Native, NativeValue, UNSUPPORTED_ARGUMENT},
+    primitives::Symbol,
+};
+
+use super::Value;
+
+/// Calls `native` with tree-walker values, converting them on the way in and
+/// the result on the way out.
+pub fn call(native: &Native, arguments: &[Value]) -> Result<Value, String> {
+    let arguments = arguments
+        .iter()
+        .map(|argument| match argument {
+            Value::Nil => Ok(NativeValue::Nil),
+            Value::Bool(b) => Ok(NativeValue::Bool(*b)),
+            Value::Number(n) => Ok(NativeValue::Number(*n)),
+            Value::String(s) => Ok(NativeValue::String(Rc::from(s.as_str()))),
+            _ => Err(UNSUPPORTED_ARGUMENT.to_string()),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(match native.call(&arguments)? {
+        NativeValue::Nil => Value::Nil,
+        NativeValue::Bool(b) => Value::Bool(b),
+        NativeValue::Number(n) => Value::Number(n),
+        NativeValue::String(s) => Value::String(Symbol::intern(&s)),
+    })
+}
@@ -1,11 +1,57 @@
-use crate::primitives::Literal;
+use std::{cell::RefCell, rc::Rc};
 
-#[derive(PartialEq)]
+use crate::primitives::{Literal, Symbol};
+
+use crate::native::Native;
+
+use super::{Class, Function, Instance};
+
+#[derive(Clone)]
 pub enum Value {
     Number(f64),
-    String(String),
+    String(Symbol),
     Bool(bool),
     Nil,
+    Function(Rc<Function>),
+    NativeFunction(Rc<Native>),
+    Class(Rc<Class>),
+    Instance(Rc<RefCell<Instance>>),
+}
+
+impl Value {
+    /// `false` and `nil` are falsey, every other value is truthy.
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Value::Bool(false) | Value::Nil)
+    }
+
+    /// The name used for this kind of value in error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Bool(_) => "boolean",
+            Value::Nil => "nil",
+            Value::Function(_) | Value::NativeFunction(_) => "function",
+            Value::Class(_) => "class",
+            Value::Instance(_) => "instance",
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Number(a), Self::Number(b)) => a == b,
+            (Self::String(a), Self::String(b)) => a == b,
+            (Self::Bool(a), Self::Bool(b)) => a == b,
+            (Self::Nil, Self::Nil) => true,
+            (Self::Function(a), Self::Function(b)) => Rc::ptr_eq(a, b),
+            (Self::NativeFunction(a), Self::NativeFunction(b)) => Rc::ptr_eq(a, b),
+            (Self::Class(a), Self::Class(b)) => Rc::ptr_eq(a, b),
+            (Self::Instance(a), Self::Instance(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
 }
 
 impl std::fmt::Display for Value {
@@ -15,6 +61,10 @@ impl std::fmt::Display for Value {
             Self::String(s) => write!(f, "{s}"),
             Self::Bool(b) => write!(f, "{b}"),
             Self::Nil => write!(f, "nil"),
+            Self::Function(function) => write!(f, "{function}"),
+            Self::NativeFunction(function) => write!(f, "{function}"),
+            Self::Class(class) => write!(f, "{class}"),
+            Self::Instance(instance) => write!(f, "{}", instance.borrow()),
         }
     }
 }
@@ -1,44 +1,157 @@
-use std::fmt::Display;
+use std::{cell::Cell, fmt::Display};
 
-use crate::primitives::Literal;
+use crate::primitives::{Literal, Span, Symbol};
 
-use super::{binary::BinaryOperator, unary::UnaryOperator};
+use super::{binary::BinaryOperator, logical::LogicalOperator, unary::UnaryOperator};
 
 #[derive(Debug, PartialEq)]
 pub enum Expr {
     Literal {
         literal: Literal,
-        line: usize,
+        span: Span,
     },
     Unary {
         operator: UnaryOperator,
         right: Box<Expr>,
+        operator_span: Span,
+        span: Span,
     },
     Binary {
         operator: BinaryOperator,
         left: Box<Expr>,
         right: Box<Expr>,
+        operator_span: Span,
+        span: Span,
     },
-    Grouping(Box<Expr>),
+    Logical {
+        operator: LogicalOperator,
+        left: Box<Expr>,
+        right: Box<Expr>,
+        span: Span,
+    },
+    Call {
+        callee: Box<Expr>,
+        arguments: Vec<Expr>,
+        span: Span,
+    },
+    Get {
+        object: Box<Expr>,
+        name: Symbol,
+        name_span: Span,
+        span: Span,
+    },
+    Set {
+        object: Box<Expr>,
+        name: Symbol,
+        value: Box<Expr>,
+        name_span: Span,
+        span: Span,
+    },
+    Grouping {
+        expr: Box<Expr>,
+        span: Span,
+    },
+    /// `depth` is filled in by the resolver: the number of scopes between
+    /// the reference and the declaration, or `None` for a global.
+    Variable {
+        name: Symbol,
+        span: Span,
+        depth: Cell<Option<usize>>,
+    },
+    Assign {
+        name: Symbol,
+        value: Box<Expr>,
+        span: Span,
+        depth: Cell<Option<usize>>,
+    },
+    This {
+        span: Span,
+        depth: Cell<Option<usize>>,
+    },
+    Super {
+        method: Symbol,
+        span: Span,
+        depth: Cell<Option<usize>>,
+    },
+    /// A string literal with `${...}` in it. The literal text between the
+    /// interpolations is kept as string `Literal` parts; empty text is left
+    /// out.
+    Interpolation {
+        parts: Vec<Expr>,
+        span: Span,
+    },
+}
+
+impl Expr {
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Literal { span, .. }
+            | Expr::Unary { span, .. }
+            | Expr::Binary { span, .. }
+            | Expr::Logical { span, .. }
+            | Expr::Call { span, .. }
+            | Expr::Get { span, .. }
+            | Expr::Set { span, .. }
+            | Expr::Grouping { span, .. }
+            | Expr::Variable { span, .. }
+            | Expr::Assign { span, .. }
+            | Expr::This { span, .. }
+            | Expr::Super { span, .. }
+            | Expr::Interpolation { span, .. } => *span,
+        }
+    }
 }
 
 impl Display for Expr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Expr::Literal {
-                literal: l,
-                line: _,
-            } => match l {
+            Expr::Literal { literal: l, .. } => match l {
                 Literal::Null => write!(f, "nil"),
                 _ => write!(f, "{l}"),
             },
-            Expr::Unary { operator, right } => write!(f, "({operator} {right})"),
+            Expr::Unary {
+                operator, right, ..
+            } => write!(f, "({operator} {right})"),
             Expr::Binary {
                 operator,
                 left,
                 right,
+                ..
+            } => write!(f, "({operator} {left} {right})"),
+            Expr::Logical {
+                operator,
+                left,
+                right,
+                ..
             } => write!(f, "({operator} {left} {right})"),
-            Expr::Grouping(e) => write!(f, "(group {})", *e),
+            Expr::Call {
+                callee, arguments, ..
+            } => {
+                write!(f, "(call {callee}")?;
+                for argument in arguments {
+                    write!(f, " {argument}")?;
+                }
+                write!(f, ")")
+            }
+            Expr::Get { object, name, .. } => write!(f, "(. {object} {name})"),
+            Expr::Set {
+                object,
+                name,
+                value,
+                ..
+            } => write!(f, "(= (. {object} {name}) {value})"),
+            Expr::Grouping { expr, .. } => write!(f, "(group {expr})"),
+            Expr::Variable { name, .. } => write!(f, "{name}"),
+            Expr::Assign { name, value, .. } => write!(f, "(= {name} {value})"),
+            Expr::This { .. } => write!(f, "this"),
+            Expr::Super { method, .. } => write!(f, "(super {method})"),
+            Expr::Interpolation { parts, .. } => {
+                write!(f, "(interpolate")?;
+                for part in parts {
+                    write!(f, " {part}")?;
+                }
+                write!(f, ")")
+            }
         }
     }
 }
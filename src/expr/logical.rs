@@ -0,0 +1,35 @@
+use std::fmt::Display;
+
+use super::{Error, Result};
+use crate::primitives::TokenType;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LogicalOperator {
+    And,
+    Or,
+}
+
+impl Display for LogicalOperator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match &self {
+                LogicalOperator::And => "and",
+                LogicalOperator::Or => "or",
+            }
+        )
+    }
+}
+
+impl TryFrom<TokenType> for LogicalOperator {
+    type Error = Error;
+
+    fn try_from(token_type: TokenType) -> Result<Self> {
+        match token_type {
+            TokenType::And => Ok(LogicalOperator::And),
+            TokenType::Or => Ok(LogicalOperator::Or),
+            _ => Err(Self::Error::InvalidTypeForLogicalOperator { token_type }),
+        }
+    }
+}
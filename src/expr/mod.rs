@@ -1,5 +1,7 @@
 pub mod binary;
 pub mod expression;
+pub mod logical;
+pub mod statement;
 pub mod unary;
 
 use crate::primitives::TokenType;
@@ -7,9 +9,11 @@ use crate::primitives::TokenType;
 pub type Result<T> = core::result::Result<T, Error>;
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum Error {
     InvalidTypeForBinaryOperator { token_type: TokenType },
     InvalidTypeForUnaryOperator { token_type: TokenType },
+    InvalidTypeForLogicalOperator { token_type: TokenType },
 }
 
 impl std::error::Error for Error {}
@@ -23,6 +27,9 @@ impl std::fmt::Display for Error {
             Error::InvalidTypeForBinaryOperator { token_type } => {
                 write!(f, "An Unary Operator can't be created from {token_type}")
             }
+            Error::InvalidTypeForLogicalOperator { token_type } => {
+                write!(f, "A Logical Operator can't be created from {token_type}")
+            }
         }
     }
 }
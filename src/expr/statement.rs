@@ -0,0 +1,55 @@
+use std::rc::Rc;
+
+use crate::primitives::{Span, Symbol};
+
+use super::expression::Expr;
+
+#[derive(Debug, PartialEq)]
+pub enum Stmt {
+    Expression(Expr),
+    Print(Expr),
+    Var {
+        name: Symbol,
+        initializer: Option<Expr>,
+        span: Span,
+    },
+    Block(Vec<Stmt>),
+    If {
+        condition: Expr,
+        then_branch: Box<Stmt>,
+        else_branch: Option<Box<Stmt>>,
+    },
+    While {
+        condition: Expr,
+        body: Box<Stmt>,
+    },
+    Function(Rc<FunctionDecl>),
+    Class(ClassDecl),
+    Return {
+        value: Option<Expr>,
+        span: Span,
+    },
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Parameter {
+    pub name: Symbol,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct FunctionDecl {
+    pub name: Symbol,
+    pub params: Vec<Parameter>,
+    pub body: Vec<Stmt>,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ClassDecl {
+    pub name: Symbol,
+    /// Always an `Expr::Variable` when present.
+    pub superclass: Option<Expr>,
+    pub methods: Vec<Rc<FunctionDecl>>,
+    pub span: Span,
+}
@@ -3,7 +3,7 @@ use std::fmt::Display;
 use super::{Error, Result};
 use crate::primitives::TokenType;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum UnaryOperator {
     Bang,
     Minus,
@@ -1,8 +1,15 @@
+mod chunk;
+mod compiler;
+mod diagnostics;
 mod evaluate;
 mod expr;
+mod native;
 mod parser;
 mod primitives;
+mod repl;
+mod resolver;
 mod scan;
+mod vm;
 
 use std::fs;
 use std::process::Termination;
@@ -10,14 +17,22 @@ use std::process::Termination;
 use crate::parser::RecursiveDescentParser;
 use clap::Parser as ClapParser;
 use clap::Subcommand;
-use evaluate::eval;
+use compiler::Compiler;
+use diagnostics::{ErrorFormat, Reporter};
+use evaluate::Interpreter;
+use resolver::Resolver;
 use scan::lexer::Lexer;
+use vm::{Heap, Vm};
 
 #[derive(ClapParser)]
 #[command(version, about, long_about = None)]
 struct Args {
     #[command(subcommand)]
-    command: Commands,
+    command: Option<Commands>,
+
+    /// How errors are printed. Defaults to `rich` when stderr is a terminal.
+    #[arg(long, global = true, value_enum)]
+    error_format: Option<ErrorFormat>,
 }
 
 #[derive(Subcommand)]
@@ -30,12 +45,49 @@ enum Commands {
 
     /// Evaluates the contents of a file.
     Evaluate { filename: String },
+
+    /// Runs the program inside the provided Filename.
+    Run {
+        filename: String,
+
+        /// Which implementation runs the program.
+        #[arg(long, value_enum, default_value_t = Backend::TreeWalk)]
+        backend: Backend,
+
+        /// Prints the stack and each instruction to stderr as the VM runs them.
+        #[arg(long)]
+        trace: bool,
+
+        /// Makes the VM collect garbage on every allocation.
+        #[arg(long)]
+        gc_stress: bool,
+
+        /// Reports how many bytes every garbage collection frees.
+        #[arg(long)]
+        gc_log: bool,
+    },
+
+    /// Compiles the provided Filename to bytecode and lists its instructions.
+    Disassemble { filename: String },
+
+    /// Starts an interactive session. This is the default when no command is given.
+    Repl,
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Backend {
+    /// Walks the syntax tree directly.
+    TreeWalk,
+    /// Compiles to bytecode and runs it on a stack machine.
+    Vm,
 }
 
 enum ProgramState {
     Success,
     LexerError,
     ParserError,
+    ResolverError,
+    CompileError,
     RuntimeException,
 }
 
@@ -45,31 +97,51 @@ impl Termination for ProgramState {
 
         match self {
             ProgramState::Success => ExitCode::SUCCESS,
-            ProgramState::LexerError | ProgramState::ParserError => ExitCode::from(65),
+            ProgramState::LexerError
+            | ProgramState::ParserError
+            | ProgramState::ResolverError
+            | ProgramState::CompileError => ExitCode::from(65),
             ProgramState::RuntimeException => ExitCode::from(70),
         }
     }
 }
 
+/// The tree-walking interpreter recurses on the native stack, several Rust
+/// frames per Lox call. This leaves room for the 1024 nested calls both
+/// backends allow, even in debug builds.
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+
 fn main() -> ProgramState {
     let args = Args::parse();
 
+    std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || run(args))
+        .expect("The interpreter thread can be spawned")
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
+
+fn run(args: Args) -> ProgramState {
     let mut status = ProgramState::Success;
+    let format = args.error_format.unwrap_or_else(ErrorFormat::detect);
 
-    match args.command {
+    match args.command.unwrap_or(Commands::Repl) {
         Commands::Tokenize { filename } => {
             let file_contents = fs::read_to_string(&filename).unwrap_or_else(|_| {
                 eprintln!("Failed to read file {}", filename);
                 String::new()
             });
 
+            let reporter = Reporter::new(&file_contents, format);
+
             // Uncomment this block to pass the first stage
             for token in Lexer::new(&file_contents) {
                 match token {
                     Ok(token) => println!("{token}"),
                     Err(e) => {
                         status = ProgramState::LexerError;
-                        eprintln!("{e}");
+                        reporter.report(&e);
                     }
                 }
             }
@@ -80,15 +152,13 @@ fn main() -> ProgramState {
                 String::new()
             });
 
-            let mut parser = RecursiveDescentParser::new(
-                Lexer::new(&file_contents).filter_map(Result::ok).collect(),
-            );
+            let mut parser = RecursiveDescentParser::from_lexer(Lexer::new(&file_contents));
 
             match parser.parse() {
                 Ok(expr) => println!("{expr}"),
-                Err(e) => {
+                Err(errors) => {
                     status = ProgramState::ParserError;
-                    eprintln!("{e}");
+                    Reporter::new(&file_contents, format).report_all(&errors);
                 }
             }
         }
@@ -98,20 +168,118 @@ fn main() -> ProgramState {
                 String::new()
             });
 
-            let mut parser = RecursiveDescentParser::new(
-                Lexer::new(&file_contents).filter_map(Result::ok).collect(),
-            );
+            let reporter = Reporter::new(&file_contents, format);
+            let mut parser = RecursiveDescentParser::from_lexer(Lexer::new(&file_contents));
 
-            let expr = parser.parse();
+            let expr = match parser.parse() {
+                Ok(expr) => expr,
+                Err(errors) => {
+                    reporter.report_all(&errors);
+                    return ProgramState::ParserError;
+                }
+            };
 
-            match eval(expr.unwrap()) {
+            match Interpreter::new().evaluate(&expr) {
                 Ok(expr) => println!("{expr}"),
                 Err(e) => {
                     status = ProgramState::RuntimeException;
-                    eprintln!("{e}");
+                    reporter.report(&e);
                 }
             }
         }
+        Commands::Run {
+            filename,
+            backend,
+            trace,
+            gc_stress,
+            gc_log,
+        } => {
+            let file_contents = fs::read_to_string(&filename).unwrap_or_else(|_| {
+                eprintln!("Failed to read file {}", filename);
+                String::new()
+            });
+
+            let reporter = Reporter::new(&file_contents, format);
+            let mut parser = RecursiveDescentParser::from_lexer(Lexer::new(&file_contents));
+
+            let program = match parser.parse_program() {
+                Ok(program) => program,
+                Err(errors) => {
+                    reporter.report_all(&errors);
+                    return ProgramState::ParserError;
+                }
+            };
+
+            if let Err(e) = Resolver::new().resolve(&program) {
+                reporter.report(&e);
+                return ProgramState::ResolverError;
+            }
+
+            match backend {
+                Backend::TreeWalk => {
+                    if let Err(e) = Interpreter::new().run(&program) {
+                        status = ProgramState::RuntimeException;
+                        reporter.report(&e);
+                    }
+                }
+                Backend::Vm => {
+                    let mut vm = Vm::new();
+                    vm.set_trace(trace);
+                    vm.set_gc_stress(gc_stress);
+                    vm.set_gc_log(gc_log);
+
+                    let script = match Compiler::new(vm.heap_mut()).compile(&program) {
+                        Ok(script) => script,
+                        Err(e) => {
+                            reporter.report(&e);
+                            return ProgramState::CompileError;
+                        }
+                    };
+
+                    if let Err(e) = vm.interpret(script) {
+                        status = ProgramState::RuntimeException;
+                        reporter.report(&e);
+                    }
+                }
+            }
+        }
+        Commands::Disassemble { filename } => {
+            let file_contents = fs::read_to_string(&filename).unwrap_or_else(|_| {
+                eprintln!("Failed to read file {}", filename);
+                String::new()
+            });
+
+            let reporter = Reporter::new(&file_contents, format);
+            let mut parser = RecursiveDescentParser::from_lexer(Lexer::new(&file_contents));
+
+            let program = match parser.parse_program() {
+                Ok(program) => program,
+                Err(errors) => {
+                    reporter.report_all(&errors);
+                    return ProgramState::ParserError;
+                }
+            };
+
+            if let Err(e) = Resolver::new().resolve(&program) {
+                reporter.report(&e);
+                return ProgramState::ResolverError;
+            }
+
+            let mut heap = Heap::new();
+            let script = match Compiler::new(&mut heap).compile(&program) {
+                Ok(script) => script,
+                Err(e) => {
+                    reporter.report(&e);
+                    return ProgramState::CompileError;
+                }
+            };
+
+            print!(
+                "{}",
+                heap.function(script).chunk.disassemble("<script>", &heap)
+            );
+        }
+        Commands::Repl => repl::start(format),
     };
 
     status
@@ -0,0 +1,69 @@
+use std::{
+    fmt::Display,
+    rc::Rc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// What a native function takes and returns. Both backends convert their own
+/// values to and from these, so a native is written once and works on both.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NativeValue {
+    Nil,
+    Bool(bool),
+    Number(f64),
+    String(Rc<str>),
+}
+
+/// The body of a native function. Natives report failures as a plain message,
+/// which the interpreter turns into a runtime error at the call site.
+pub type NativeFn = dyn Fn(&[NativeValue]) -> Result<NativeValue, String>;
+
+/// A function written in Rust that scripts call like any other. Register it
+/// with `Interpreter::define_native` or `Vm::define_native`.
+#[derive(Clone)]
+pub struct Native {
+    pub name: String,
+    pub arity: usize,
+    function: Rc<NativeFn>,
+}
+
+impl Native {
+    pub fn new<F>(name: &str, arity: usize, function: F) -> Self
+    where
+        F: Fn(&[NativeValue]) -> Result<NativeValue, String> + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            arity,
+            function: Rc::new(function),
+        }
+    }
+
+    pub fn call(&self, arguments: &[NativeValue]) -> Result<NativeValue, String> {
+        (self.function)(arguments)
+    }
+}
+
+impl Display for Native {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+/// The message for an argument natives cannot take, like a function or an
+/// instance.
+pub const UNSUPPORTED_ARGUMENT: &str = "natives only take nil, booleans, numbers and strings";
+
+/// The natives every interpreter starts with.
+pub fn standard() -> Vec<Native> {
+    vec![Native::new("clock", 0, clock)]
+}
+
+/// Seconds elapsed since the UNIX epoch.
+fn clock(_: &[NativeValue]) -> Result<NativeValue, String> {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| e.to_string())?;
+
+    Ok(NativeValue::Number(elapsed.as_secs_f64()))
+}
@@ -1,26 +1,133 @@
 use std::fmt::Display;
 
+use crate::{
+    diagnostics::{Diagnostic, ToDiagnostic},
+    primitives::{Span, Token, TokenType},
+    scan,
+};
+
 pub mod rd_parser;
 
 pub use rd_parser::RecursiveDescentParser;
 
 pub type Result<T> = core::result::Result<T, Error>;
 
-#[derive(Debug)]
+/// The token a syntax error was detected at.
+#[derive(Debug, Clone)]
+pub struct Found {
+    pub lexeme: String,
+    pub span: Span,
+    pub at_end: bool,
+}
+
+impl From<&Token<'_>> for Found {
+    fn from(token: &Token<'_>) -> Self {
+        Self {
+            lexeme: token.lexeme.to_string(),
+            span: token.span,
+            at_end: token.token_type == TokenType::EndOfFile,
+        }
+    }
+}
+
+impl Display for Found {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.at_end {
+            write!(f, "at end")
+        } else {
+            write!(f, "at '{}'", self.lexeme)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum Error {
-    UnexpectedToken { line: usize, lexeme: String },
-    MissingToken,
+    /// Lexer errors are reported alongside the syntax errors found after them.
+    Scan(scan::Error),
+    /// The grammar requires a specific token here. `expected` is the message
+    /// saying which one, e.g. "Expect ')' after expression.".
+    ExpectedToken {
+        expected: &'static str,
+        found: Found,
+    },
+    ExpectedExpression {
+        found: Found,
+    },
+    InvalidAssignmentTarget {
+        found: Found,
+    },
+    /// `brace` is the `{` that was never closed.
+    UnclosedBlock {
+        brace: Span,
+        found: Found,
+    },
+    TooManyArguments {
+        found: Found,
+    },
+    TooManyParameters {
+        found: Found,
+    },
+}
+
+impl Error {
+    /// The token the error is reported at and the reference message, or the
+    /// lexer error being carried.
+    fn parts(&self) -> core::result::Result<(&Found, &'static str), &scan::Error> {
+        match self {
+            Error::Scan(e) => Err(e),
+            Error::ExpectedToken { expected, found } => Ok((found, expected)),
+            Error::ExpectedExpression { found } => Ok((found, "Expect expression.")),
+            Error::InvalidAssignmentTarget { found } => Ok((found, "Invalid assignment target.")),
+            Error::UnclosedBlock { found, .. } => Ok((found, "Expect '}' after block.")),
+            Error::TooManyArguments { found } => Ok((found, "Can't have more than 255 arguments.")),
+            Error::TooManyParameters { found } => {
+                Ok((found, "Can't have more than 255 parameters."))
+            }
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self.parts() {
+            Ok((found, _)) => found.span,
+            Err(e) => e.span(),
+        }
+    }
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Error::UnexpectedToken { line, lexeme } => {
-                write!(f, "[line {line}] error at '{lexeme}': Expect expression.")
+        match self.parts() {
+            Ok((found, message)) => {
+                write!(f, "[line {}] Error {found}: {message}", found.span.line)
             }
-            Error::MissingToken => write!(f, "A token was expected, but none were found."),
+            Err(e) => write!(f, "{e}"),
         }
     }
 }
 
 impl std::error::Error for Error {}
+
+impl From<scan::Error> for Error {
+    fn from(e: scan::Error) -> Self {
+        Error::Scan(e)
+    }
+}
+
+impl ToDiagnostic for Error {
+    fn diagnostic(&self) -> Diagnostic {
+        let (found, message) = match self.parts() {
+            Ok(parts) => parts,
+            Err(e) => return e.diagnostic(),
+        };
+
+        let diagnostic = Diagnostic::error(message, found.span);
+
+        match self {
+            Error::UnclosedBlock { brace, .. } => {
+                diagnostic.with_label(*brace, "the block opened here is never closed")
+            }
+            _ if found.at_end => diagnostic.with_note("the file ended unexpectedly"),
+            _ => diagnostic,
+        }
+    }
+}
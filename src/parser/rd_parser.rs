@@ -1,36 +1,548 @@
-use std::collections::VecDeque;
+use std::{cell::Cell, collections::VecDeque, rc::Rc};
 
 use crate::{
-    expr::expression::Expr,
-    primitives::{Literal, Token, TokenType},
+    expr::{
+        expression::Expr,
+        statement::{ClassDecl, FunctionDecl, Parameter, Stmt},
+    },
+    primitives::{Literal, Span, Symbol, Token, TokenType},
+    scan::lexer::Lexer,
 };
 
 use super::{Error, Result};
 
+/// Lox caps the number of arguments of a call and parameters of a function.
+const MAX_ARGUMENTS: usize = 255;
+
+#[derive(Clone, Copy)]
+enum FunctionKind {
+    Function,
+    Method,
+}
+
 #[derive(Clone)]
 pub struct RecursiveDescentParser<'a> {
     tokens: VecDeque<Token<'a>>,
+    /// Every error found so far. Parsing goes on after an error so that a
+    /// single run reports as many of them as possible.
+    errors: Vec<Error>,
 }
 
 impl<'a> RecursiveDescentParser<'a> {
     pub fn new(tokens: Vec<Token<'a>>) -> Self {
         let mut tokens: VecDeque<_> = tokens.into();
-        if let Some(last) = tokens.pop_back() {
-            // If the last token is EOF, we delete it.
-            if last.token_type != TokenType::EndOfFile {
-                tokens.push_back(last);
+
+        // Errors at the end of the input are reported at the EOF token, so
+        // there must always be one.
+        if tokens
+            .back()
+            .map_or(true, |last| last.token_type != TokenType::EndOfFile)
+        {
+            let end = tokens.back().map_or(Span::default(), |last| last.span);
+            let span = Span::new(end.end, end.end, end.line, end.column);
+            tokens.push_back(Token::new(TokenType::EndOfFile, "", Literal::Null, span));
+        }
+
+        Self {
+            tokens,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Parses the tokens of `lexer`, keeping its errors to be reported along
+    /// with the syntax errors.
+    pub fn from_lexer(lexer: Lexer<'a>) -> Self {
+        let mut tokens = Vec::new();
+        let mut errors = Vec::new();
+
+        for token in lexer {
+            match token {
+                Ok(token) => tokens.push(token),
+                Err(e) => errors.push(e.into()),
+            }
+        }
+
+        Self {
+            errors,
+            ..Self::new(tokens)
+        }
+    }
+
+    pub fn parse(&mut self) -> core::result::Result<Expr, Vec<Error>> {
+        match self.expression() {
+            Ok(expr) if self.errors.is_empty() => Ok(expr),
+            Ok(_) => Err(self.take_errors()),
+            Err(e) => {
+                self.errors.push(e);
+                Err(self.take_errors())
             }
         }
+    }
+
+    /// The errors found so far in source order. Lexer errors are all
+    /// collected before parsing starts, so they have to be merged in.
+    fn take_errors(&mut self) -> Vec<Error> {
+        let mut errors = std::mem::take(&mut self.errors);
+        errors.sort_by_key(|e| e.span().start);
+        errors
+    }
 
-        Self { tokens }
+    pub fn is_at_end(&self) -> bool {
+        self.peek().token_type == TokenType::EndOfFile
     }
 
-    pub fn parse(&'a mut self) -> Result<Expr> {
-        self.expression()
+    /// The EOF token is never consumed, so there is always a next token.
+    fn peek(&self) -> &Token<'a> {
+        self.tokens
+            .front()
+            .expect("The EOF token is never consumed.")
+    }
+
+    pub fn parse_program(&mut self) -> core::result::Result<Vec<Stmt>, Vec<Error>> {
+        let mut statements = Vec::new();
+
+        while !self.is_at_end() {
+            if let Some(stmt) = self.declaration_or_recover() {
+                statements.push(stmt);
+            }
+        }
+
+        if self.errors.is_empty() {
+            Ok(statements)
+        } else {
+            Err(self.take_errors())
+        }
+    }
+
+    /// Records the error of a failed declaration and skips to the start of
+    /// the next statement, so parsing can resume from there.
+    fn declaration_or_recover(&mut self) -> Option<Stmt> {
+        match self.declaration() {
+            Ok(stmt) => Some(stmt),
+            Err(e) => {
+                self.errors.push(e);
+                self.synchronize();
+                None
+            }
+        }
+    }
+
+    /// Panic mode: discards tokens until a statement boundary, either right
+    /// after a `;` or before a keyword that starts a statement.
+    fn synchronize(&mut self) {
+        while let Some(token) = self.tokens.front() {
+            match token.token_type {
+                TokenType::Class
+                | TokenType::Fun
+                | TokenType::Var
+                | TokenType::For
+                | TokenType::If
+                | TokenType::While
+                | TokenType::Print
+                | TokenType::Return
+                | TokenType::EndOfFile => return,
+                TokenType::SemiColon => {
+                    self.tokens.pop_front();
+                    return;
+                }
+                _ => {
+                    self.tokens.pop_front();
+                }
+            }
+        }
+    }
+
+    fn declaration(&mut self) -> Result<Stmt> {
+        if self.matches_type(vec![TokenType::Var]) {
+            self.tokens.pop_front();
+
+            return self.var_declaration();
+        }
+
+        if self.matches_type(vec![TokenType::Fun]) {
+            self.tokens.pop_front();
+
+            return Ok(Stmt::Function(Rc::new(
+                self.function(FunctionKind::Function)?,
+            )));
+        }
+
+        if self.matches_type(vec![TokenType::Class]) {
+            self.tokens.pop_front();
+
+            return self.class_declaration();
+        }
+
+        self.statement()
+    }
+
+    fn class_declaration(&mut self) -> Result<Stmt> {
+        let name = self.consume(TokenType::Identifier, "Expect class name.")?;
+
+        let mut superclass = None;
+        if self.matches_type(vec![TokenType::Less]) {
+            self.tokens.pop_front();
+
+            let superclass_name = self.consume(TokenType::Identifier, "Expect superclass name.")?;
+            superclass = Some(Expr::Variable {
+                name: Symbol::intern(superclass_name.lexeme),
+                span: superclass_name.span,
+                depth: Cell::default(),
+            });
+        }
+
+        self.consume(TokenType::LeftBracket, "Expect '{' before class body.")?;
+
+        let mut methods = Vec::new();
+        while !self.matches_type(vec![TokenType::RightBracket]) && !self.is_at_end() {
+            methods.push(Rc::new(self.function(FunctionKind::Method)?));
+        }
+        self.consume(TokenType::RightBracket, "Expect '}' after class body.")?;
+
+        Ok(Stmt::Class(ClassDecl {
+            name: Symbol::intern(name.lexeme),
+            superclass,
+            methods,
+            span: name.span,
+        }))
+    }
+
+    fn function(&mut self, kind: FunctionKind) -> Result<FunctionDecl> {
+        let (name_message, paren_message) = match kind {
+            FunctionKind::Function => ("Expect function name.", "Expect '(' after function name."),
+            FunctionKind::Method => ("Expect method name.", "Expect '(' after method name."),
+        };
+
+        let name = self.consume(TokenType::Identifier, name_message)?;
+        self.consume(TokenType::LeftParenthesis, paren_message)?;
+
+        let mut params = Vec::new();
+        if !self.matches_type(vec![TokenType::RightParenthesis]) {
+            loop {
+                let param = self.consume(TokenType::Identifier, "Expect parameter name.")?;
+
+                // Reported without giving up on the declaration, the parser
+                // is not confused.
+                if params.len() >= MAX_ARGUMENTS {
+                    self.errors.push(Error::TooManyParameters {
+                        found: (&param).into(),
+                    });
+                }
+                params.push(Parameter {
+                    name: Symbol::intern(param.lexeme),
+                    span: param.span,
+                });
+
+                if !self.matches_type(vec![TokenType::Comma]) {
+                    break;
+                }
+                self.tokens.pop_front();
+            }
+        }
+        self.consume(TokenType::RightParenthesis, "Expect ')' after parameters.")?;
+
+        let brace = self.consume(TokenType::LeftBracket, "Expect '{' before function body.")?;
+        let body = self.block(brace.span)?;
+
+        Ok(FunctionDecl {
+            name: Symbol::intern(name.lexeme),
+            params,
+            body,
+            span: name.span,
+        })
+    }
+
+    fn var_declaration(&mut self) -> Result<Stmt> {
+        let name = self.consume(TokenType::Identifier, "Expect variable name.")?;
+
+        let mut initializer = None;
+        if self.matches_type(vec![TokenType::Equal]) {
+            self.tokens.pop_front();
+
+            initializer = Some(self.expression()?);
+        }
+
+        self.consume(
+            TokenType::SemiColon,
+            "Expect ';' after variable declaration.",
+        )?;
+
+        Ok(Stmt::Var {
+            name: Symbol::intern(name.lexeme),
+            initializer,
+            span: name.span,
+        })
+    }
+
+    fn statement(&mut self) -> Result<Stmt> {
+        if self.matches_type(vec![TokenType::Print]) {
+            self.tokens.pop_front();
+
+            let expr = self.expression()?;
+            self.consume(TokenType::SemiColon, "Expect ';' after value.")?;
+
+            return Ok(Stmt::Print(expr));
+        }
+
+        if self.matches_type(vec![TokenType::Return]) {
+            let keyword = self.tokens.pop_front().expect("We just checked.");
+
+            let mut value = None;
+            if !self.matches_type(vec![TokenType::SemiColon]) {
+                value = Some(self.expression()?);
+            }
+            self.consume(TokenType::SemiColon, "Expect ';' after return value.")?;
+
+            return Ok(Stmt::Return {
+                value,
+                span: keyword.span,
+            });
+        }
+
+        if self.matches_type(vec![TokenType::If]) {
+            self.tokens.pop_front();
+
+            return self.if_statement();
+        }
+
+        if self.matches_type(vec![TokenType::While]) {
+            self.tokens.pop_front();
+
+            return self.while_statement();
+        }
+
+        if self.matches_type(vec![TokenType::For]) {
+            self.tokens.pop_front();
+
+            return self.for_statement();
+        }
+
+        if self.matches_type(vec![TokenType::LeftBracket]) {
+            let token = self.tokens.pop_front().expect("We just checked.");
+
+            return Ok(Stmt::Block(self.block(token.span)?));
+        }
+
+        let expr = self.expression()?;
+        self.consume(TokenType::SemiColon, "Expect ';' after expression.")?;
+
+        Ok(Stmt::Expression(expr))
+    }
+
+    fn if_statement(&mut self) -> Result<Stmt> {
+        self.consume(TokenType::LeftParenthesis, "Expect '(' after 'if'.")?;
+        let condition = self.expression()?;
+        self.consume(
+            TokenType::RightParenthesis,
+            "Expect ')' after if condition.",
+        )?;
+
+        let then_branch = Box::new(self.statement()?);
+
+        let mut else_branch = None;
+        if self.matches_type(vec![TokenType::Else]) {
+            self.tokens.pop_front();
+
+            else_branch = Some(Box::new(self.statement()?));
+        }
+
+        Ok(Stmt::If {
+            condition,
+            then_branch,
+            else_branch,
+        })
+    }
+
+    fn while_statement(&mut self) -> Result<Stmt> {
+        self.consume(TokenType::LeftParenthesis, "Expect '(' after 'while'.")?;
+        let condition = self.expression()?;
+        self.consume(TokenType::RightParenthesis, "Expect ')' after condition.")?;
+
+        let body = Box::new(self.statement()?);
+
+        Ok(Stmt::While { condition, body })
+    }
+
+    /// There is no `for` statement in the AST: it is desugared into an
+    /// equivalent `while` loop wrapped in a block.
+    fn for_statement(&mut self) -> Result<Stmt> {
+        let paren = self.consume(TokenType::LeftParenthesis, "Expect '(' after 'for'.")?;
+
+        let initializer = if self.matches_type(vec![TokenType::SemiColon]) {
+            self.tokens.pop_front();
+            None
+        } else if self.matches_type(vec![TokenType::Var]) {
+            self.tokens.pop_front();
+            Some(self.var_declaration()?)
+        } else {
+            let expr = self.expression()?;
+            self.consume(TokenType::SemiColon, "Expect ';' after expression.")?;
+            Some(Stmt::Expression(expr))
+        };
+
+        let condition = if self.matches_type(vec![TokenType::SemiColon]) {
+            Expr::Literal {
+                literal: Literal::True,
+                span: paren.span,
+            }
+        } else {
+            self.expression()?
+        };
+        self.consume(TokenType::SemiColon, "Expect ';' after loop condition.")?;
+
+        let increment = if self.matches_type(vec![TokenType::RightParenthesis]) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume(TokenType::RightParenthesis, "Expect ')' after for clauses.")?;
+
+        let mut body = self.statement()?;
+
+        if let Some(increment) = increment {
+            body = Stmt::Block(vec![body, Stmt::Expression(increment)]);
+        }
+
+        body = Stmt::While {
+            condition,
+            body: Box::new(body),
+        };
+
+        if let Some(initializer) = initializer {
+            body = Stmt::Block(vec![initializer, body]);
+        }
+
+        Ok(body)
+    }
+
+    /// `brace` is the span of the opening brace, reported if it is never closed.
+    fn block(&mut self, brace: Span) -> Result<Vec<Stmt>> {
+        let mut statements = Vec::new();
+
+        while !self.matches_type(vec![TokenType::RightBracket]) && !self.is_at_end() {
+            if let Some(stmt) = self.declaration_or_recover() {
+                statements.push(stmt);
+            }
+        }
+
+        if self.is_at_end() {
+            return Err(Error::UnclosedBlock {
+                brace,
+                found: self.peek().into(),
+            });
+        }
+        self.tokens.pop_front();
+
+        Ok(statements)
+    }
+
+    fn consume(&mut self, token_type: TokenType, message: &'static str) -> Result<Token<'a>> {
+        // The unexpected token is left in place for `synchronize` to look at.
+        if self.peek().token_type == token_type {
+            return Ok(self.tokens.pop_front().expect("We just checked."));
+        }
+
+        Err(Error::ExpectedToken {
+            expected: message,
+            found: self.peek().into(),
+        })
     }
 
     fn expression(&mut self) -> Result<Expr> {
-        self.equality()
+        self.assignment()
+    }
+
+    fn assignment(&mut self) -> Result<Expr> {
+        let expr = self.or()?;
+
+        if self.matches_type(vec![TokenType::Equal]) {
+            let equals = self.tokens.pop_front().expect("We just checked.");
+            let value = Box::new(self.assignment()?);
+
+            let span = expr.span().to(value.span());
+
+            return match expr {
+                Expr::Variable { name, .. } => Ok(Expr::Assign {
+                    name,
+                    value,
+                    span,
+                    depth: Cell::default(),
+                }),
+                Expr::Get {
+                    object,
+                    name,
+                    name_span,
+                    ..
+                } => Ok(Expr::Set {
+                    object,
+                    name,
+                    value,
+                    name_span,
+                    span,
+                }),
+                _ => {
+                    // Reported without unwinding, the parser is not confused.
+                    self.errors.push(Error::InvalidAssignmentTarget {
+                        found: (&equals).into(),
+                    });
+                    Ok(*value)
+                }
+            };
+        }
+
+        Ok(expr)
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut expr = self.and()?;
+
+        while self.matches_type(vec![TokenType::Or]) {
+            let operator = self
+                .tokens
+                .pop_front()
+                .expect("This can't be None, we just checked.")
+                .token_type
+                .try_into()
+                .expect("This is a valid Token Type");
+
+            let right = Box::new(self.and()?);
+            let span = expr.span().to(right.span());
+
+            expr = Expr::Logical {
+                operator,
+                left: Box::new(expr),
+                right,
+                span,
+            };
+        }
+
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut expr = self.equality()?;
+
+        while self.matches_type(vec![TokenType::And]) {
+            let operator = self
+                .tokens
+                .pop_front()
+                .expect("This can't be None, we just checked.")
+                .token_type
+                .try_into()
+                .expect("This is a valid Token Type");
+
+            let right = Box::new(self.equality()?);
+            let span = expr.span().to(right.span());
+
+            expr = Expr::Logical {
+                operator,
+                left: Box::new(expr),
+                right,
+                span,
+            };
+        }
+
+        Ok(expr)
     }
 
     fn equality(&mut self) -> Result<Expr> {
@@ -38,20 +550,24 @@ impl<'a> RecursiveDescentParser<'a> {
 
         // Esto tiene que estar mal.
         while self.matches_type(vec![TokenType::BangEqual, TokenType::EqualEqual]) {
-            let operator = self
+            let token = self
                 .tokens
                 .pop_front()
-                .expect("This can't be None, we just checked.")
+                .expect("This can't be None, we just checked.");
+            let operator = token
                 .token_type
                 .try_into()
                 .expect("This is a valid Token Type");
 
             let right = Box::new(self.comparison()?);
+            let span = expr.span().to(right.span());
 
             expr = Expr::Binary {
                 operator,
                 left: Box::new(expr),
                 right,
+                operator_span: token.span,
+                span,
             };
         }
 
@@ -67,20 +583,24 @@ impl<'a> RecursiveDescentParser<'a> {
             TokenType::Less,
             TokenType::LessEqual,
         ]) {
-            let operator = self
+            let token = self
                 .tokens
                 .pop_front()
-                .expect("This can't be None, we just checked.")
+                .expect("This can't be None, we just checked.");
+            let operator = token
                 .token_type
                 .try_into()
                 .expect("This is a valid Token Type");
 
             let right = Box::new(self.term()?);
+            let span = expr.span().to(right.span());
 
             expr = Expr::Binary {
                 operator,
                 left: Box::new(expr),
                 right,
+                operator_span: token.span,
+                span,
             }
         }
 
@@ -91,20 +611,24 @@ impl<'a> RecursiveDescentParser<'a> {
         let mut expr = self.factor()?;
 
         while self.matches_type(vec![TokenType::Minus, TokenType::Plus]) {
-            let operator = self
+            let token = self
                 .tokens
                 .pop_front()
-                .expect("This can't be None, we just checked.")
+                .expect("This can't be None, we just checked.");
+            let operator = token
                 .token_type
                 .try_into()
                 .expect("This is a valid Token Type");
 
             let right = Box::new(self.factor()?);
+            let span = expr.span().to(right.span());
 
             expr = Expr::Binary {
                 operator,
                 left: Box::new(expr),
                 right,
+                operator_span: token.span,
+                span,
             };
         }
 
@@ -115,20 +639,24 @@ impl<'a> RecursiveDescentParser<'a> {
         let mut expr = self.unary()?;
 
         while self.matches_type(vec![TokenType::Slash, TokenType::Asterisk]) {
-            let operator = self
+            let token = self
                 .tokens
                 .pop_front()
-                .expect("This can't be None, we just checked.")
+                .expect("This can't be None, we just checked.");
+            let operator = token
                 .token_type
                 .try_into()
                 .expect("This is a valid Token Type");
 
             let right = Box::new(self.unary()?);
+            let span = expr.span().to(right.span());
 
             expr = Expr::Binary {
                 operator,
                 left: Box::new(expr),
                 right,
+                operator_span: token.span,
+                span,
             };
         }
 
@@ -137,75 +665,204 @@ impl<'a> RecursiveDescentParser<'a> {
 
     fn unary(&mut self) -> Result<Expr> {
         if self.matches_type(vec![TokenType::Bang, TokenType::Minus]) {
-            let operator = self
+            let token = self
                 .tokens
                 .pop_front()
-                .expect("This can't be None, we just checked.")
+                .expect("This can't be None, we just checked.");
+            let operator = token
                 .token_type
                 .try_into()
                 .expect("This is a valid Token Type");
 
             let right = Box::new(self.unary()?);
+            let span = token.span.to(right.span());
 
-            return Ok(Expr::Unary { operator, right });
+            return Ok(Expr::Unary {
+                operator,
+                right,
+                operator_span: token.span,
+                span,
+            });
         }
-        self.primary()
+        self.call()
+    }
+
+    fn call(&mut self) -> Result<Expr> {
+        let mut expr = self.primary()?;
+
+        loop {
+            if self.matches_type(vec![TokenType::LeftParenthesis]) {
+                self.tokens.pop_front();
+
+                expr = self.finish_call(expr)?;
+            } else if self.matches_type(vec![TokenType::Dot]) {
+                self.tokens.pop_front();
+
+                let name =
+                    self.consume(TokenType::Identifier, "Expect property name after '.'.")?;
+                let span = expr.span().to(name.span);
+                expr = Expr::Get {
+                    object: Box::new(expr),
+                    name: Symbol::intern(name.lexeme),
+                    name_span: name.span,
+                    span,
+                };
+            } else {
+                break;
+            }
+        }
+
+        Ok(expr)
+    }
+
+    fn finish_call(&mut self, callee: Expr) -> Result<Expr> {
+        let mut arguments = Vec::new();
+
+        if !self.matches_type(vec![TokenType::RightParenthesis]) {
+            loop {
+                if arguments.len() >= MAX_ARGUMENTS {
+                    self.errors.push(Error::TooManyArguments {
+                        found: self.peek().into(),
+                    });
+                }
+                arguments.push(self.expression()?);
+
+                if !self.matches_type(vec![TokenType::Comma]) {
+                    break;
+                }
+                self.tokens.pop_front();
+            }
+        }
+
+        let paren = self.consume(TokenType::RightParenthesis, "Expect ')' after arguments.")?;
+
+        let span = callee.span().to(paren.span);
+
+        Ok(Expr::Call {
+            callee: Box::new(callee),
+            arguments,
+            span,
+        })
     }
 
     fn primary(&mut self) -> Result<Expr> {
-        let token = self.tokens.pop_front().ok_or(Error::MissingToken)?;
+        if self.is_at_end() {
+            return Err(Error::ExpectedExpression {
+                found: self.peek().into(),
+            });
+        }
+        let token = self.tokens.pop_front().expect("We just checked.");
 
         if token.token_type == TokenType::False {
             return Ok(Expr::Literal {
                 literal: Literal::False,
-                line: token.line,
+                span: token.span,
             });
         }
 
         if token.token_type == TokenType::True {
             return Ok(Expr::Literal {
                 literal: Literal::True,
-                line: token.line,
+                span: token.span,
             });
         }
 
         if token.token_type == TokenType::Nil {
             return Ok(Expr::Literal {
                 literal: Literal::Null,
-                line: token.line,
+                span: token.span,
             });
         }
 
         if token.token_type == TokenType::Number || token.token_type == TokenType::String {
             return Ok(Expr::Literal {
                 literal: token.literal.to_owned(),
-                line: token.line,
+                span: token.span,
+            });
+        }
+
+        if token.token_type == TokenType::StringStart {
+            return self.interpolation(token);
+        }
+
+        if token.token_type == TokenType::Identifier {
+            return Ok(Expr::Variable {
+                name: Symbol::intern(token.lexeme),
+                span: token.span,
+                depth: Cell::default(),
+            });
+        }
+
+        if token.token_type == TokenType::This {
+            return Ok(Expr::This {
+                span: token.span,
+                depth: Cell::default(),
+            });
+        }
+
+        if token.token_type == TokenType::Super {
+            self.consume(TokenType::Dot, "Expect '.' after 'super'.")?;
+            let method = self.consume(TokenType::Identifier, "Expect superclass method name.")?;
+
+            return Ok(Expr::Super {
+                method: Symbol::intern(method.lexeme),
+                span: token.span.to(method.span),
+                depth: Cell::default(),
             });
         }
 
         if token.token_type == TokenType::LeftParenthesis {
             let expr = Box::new(self.expression()?);
+            let paren =
+                self.consume(TokenType::RightParenthesis, "Expect ')' after expression.")?;
 
-            if self
-                .tokens
-                .pop_front()
-                .ok_or(Error::MissingToken)?
-                .token_type
-                != TokenType::RightParenthesis
-            {
-                return Err(Error::UnexpectedToken {
-                    line: token.line,
-                    lexeme: token.lexeme.into(),
+            return Ok(Expr::Grouping {
+                expr,
+                span: token.span.to(paren.span),
+            });
+        }
+
+        let error = Error::ExpectedExpression {
+            found: (&token).into(),
+        };
+        // Put it back, it may be where `synchronize` should stop.
+        self.tokens.push_front(token);
+
+        Err(error)
+    }
+
+    /// The lexer splits `"a ${x} b ${y} c"` into a `StringStart`, a
+    /// `StringMiddle` between the expressions and a `StringEnd`.
+    fn interpolation(&mut self, first: Token) -> Result<Expr> {
+        let mut parts = Vec::new();
+        let mut segment = first;
+        let span = segment.span;
+
+        loop {
+            if !matches!(&segment.literal, Literal::String(text) if text.is_empty()) {
+                parts.push(Expr::Literal {
+                    literal: segment.literal,
+                    span: segment.span,
                 });
             }
 
-            return Ok(Expr::Grouping(expr));
-        }
+            if segment.token_type == TokenType::StringEnd {
+                return Ok(Expr::Interpolation {
+                    parts,
+                    span: span.to(segment.span),
+                });
+            }
 
-        Err(Error::UnexpectedToken {
-            line: token.line,
-            lexeme: token.lexeme.into(),
-        })
+            parts.push(self.expression()?);
+
+            if !self.matches_type(vec![TokenType::StringMiddle, TokenType::StringEnd]) {
+                return Err(Error::ExpectedToken {
+                    expected: "Expect '}' after interpolated expression.",
+                    found: self.peek().into(),
+                });
+            }
+            segment = self.tokens.pop_front().expect("We just checked.");
+        }
     }
 
     fn matches_type(&mut self, types: Vec<TokenType>) -> bool {
@@ -1,7 +1,9 @@
+use super::Symbol;
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum Literal {
     Null,
-    String(String),
+    String(Symbol),
     Number(f64),
     False,
     True,
@@ -1,7 +1,11 @@
 mod literal;
+mod span;
+mod symbol;
 mod token;
 mod token_type;
 
 pub use literal::Literal;
+pub use span::Span;
+pub use symbol::Symbol;
 pub use token::Token;
 pub use token_type::TokenType;
@@ -0,0 +1,33 @@
+/// A region of the source code. `start` and `end` are byte offsets into the
+/// source, `line` and `column` (both starting at 1) locate `start`. Columns
+/// count characters, so they stay meaningful for non-ASCII lines.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Self {
+        Self {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+
+    /// The smallest span covering both `self` and `other`.
+    pub fn to(self, other: Span) -> Span {
+        if other.start < self.start {
+            return other.to(self);
+        }
+
+        Span {
+            end: self.end.max(other.end),
+            ..self
+        }
+    }
+}
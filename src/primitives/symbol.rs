@@ -0,0 +1,87 @@
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    fmt::{Debug, Display},
+    hash::{Hash, Hasher},
+    ops::Deref,
+    rc::Rc,
+};
+
+thread_local! {
+    /// Every string that currently has a `Symbol`. An entry is removed when
+    /// the last symbol pointing to it is dropped.
+    static INTERNER: RefCell<HashSet<Rc<str>>> = RefCell::new(HashSet::new());
+}
+
+/// An interned string. Symbols with the same contents share one allocation,
+/// so comparing and hashing them only looks at the pointer.
+#[derive(Clone)]
+pub struct Symbol(Rc<str>);
+
+impl Symbol {
+    pub fn intern(string: &str) -> Self {
+        INTERNER.with(|interner| {
+            let mut interner = interner.borrow_mut();
+
+            if let Some(existing) = interner.get(string) {
+                return Symbol(Rc::clone(existing));
+            }
+
+            let string: Rc<str> = Rc::from(string);
+            interner.insert(Rc::clone(&string));
+
+            Symbol(string)
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Drop for Symbol {
+    fn drop(&mut self) {
+        // The other reference is the interner's own: this is the last symbol.
+        if Rc::strong_count(&self.0) == 2 {
+            let _ = INTERNER.try_with(|interner| {
+                if let Ok(mut interner) = interner.try_borrow_mut() {
+                    interner.remove(&*self.0);
+                }
+            });
+        }
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Rc::as_ptr(&self.0).cast::<u8>().hash(state);
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Debug for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", &*self.0)
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", &*self.0)
+    }
+}
@@ -1,21 +1,21 @@
 use std::fmt::Display;
 
-use super::{Literal, TokenType};
+use super::{Literal, Span, TokenType};
 #[derive(Clone, Debug)]
 pub struct Token<'a> {
     pub token_type: TokenType,
     pub lexeme: &'a str,
     pub literal: Literal,
-    pub line: usize,
+    pub span: Span,
 }
 
 impl<'a> Token<'a> {
-    pub fn new(token_type: TokenType, lexeme: &'a str, literal: Literal, line: usize) -> Self {
+    pub fn new(token_type: TokenType, lexeme: &'a str, literal: Literal, span: Span) -> Self {
         Self {
             token_type,
             lexeme,
             literal,
-            line,
+            span,
         }
     }
 }
@@ -25,6 +25,12 @@ pub enum TokenType {
 
     // Literals
     String,
+    /// The segments of a string literal with `${...}` in it: up to the
+    /// first `${`, between a `}` and the next `${`, and from the last `}`
+    /// to the closing quote.
+    StringStart,
+    StringMiddle,
+    StringEnd,
     Number,
     Identifier,
     And,
@@ -74,6 +80,9 @@ impl std::fmt::Display for TokenType {
                 TokenType::Less => "LESS",
                 TokenType::LessEqual => "LESS_EQUAL",
                 TokenType::String => "STRING",
+                TokenType::StringStart => "STRING_START",
+                TokenType::StringMiddle => "STRING_MIDDLE",
+                TokenType::StringEnd => "STRING_END",
                 TokenType::Number => "NUMBER",
                 TokenType::Identifier => "IDENTIFIER",
                 TokenType::EndOfFile => "EOF",
@@ -0,0 +1,114 @@
+use std::io::{self, BufRead, Write};
+
+use crate::{
+    diagnostics::{ErrorFormat, Reporter},
+    evaluate::Interpreter,
+    expr::statement::Stmt,
+    parser::RecursiveDescentParser,
+    primitives::TokenType,
+    resolver::Resolver,
+    scan::{lexer::Lexer, Error as LexerError},
+};
+
+/// Reads entries from stdin until EOF, running each one against the same
+/// interpreter so declarations persist between them.
+///
+/// Every entry is appended to one session text and scanned in place, so
+/// spans and line numbers count from the start of the session. A function
+/// declared in an earlier entry is then reported against its own source.
+pub fn start(format: ErrorFormat) {
+    let mut interpreter = Interpreter::new();
+    let mut stdin = io::stdin().lock();
+    let mut session = String::new();
+    let mut line = 1;
+    let mut entry = String::new();
+
+    loop {
+        print!("{}", if entry.is_empty() { "> " } else { "... " });
+        io::stdout().flush().expect("Stdout can be flushed");
+
+        match stdin.read_line(&mut entry) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("Failed to read input: {e}");
+                break;
+            }
+        }
+
+        if is_incomplete(&entry) {
+            continue;
+        }
+
+        let start = session.len();
+        session.push_str(&entry);
+        run_entry(&mut interpreter, &session, start, line, format);
+
+        line += entry.matches('\n').count();
+        entry.clear();
+    }
+
+    println!();
+}
+
+/// An entry keeps reading lines while it has unclosed parentheses, braces or
+/// strings.
+fn is_incomplete(entry: &str) -> bool {
+    let mut depth = 0isize;
+
+    for token in Lexer::new(entry) {
+        match token {
+            Ok(token) => match token.token_type {
+                TokenType::LeftParenthesis | TokenType::LeftBracket => depth += 1,
+                TokenType::RightParenthesis | TokenType::RightBracket => depth -= 1,
+                _ => {}
+            },
+            Err(LexerError::UnterminatedString { .. }) => return true,
+            Err(_) => {}
+        }
+    }
+
+    depth > 0
+}
+
+/// Runs the entry that starts at byte `start`, on line `line`, of `session`.
+fn run_entry(
+    interpreter: &mut Interpreter,
+    session: &str,
+    start: usize,
+    line: usize,
+    format: ErrorFormat,
+) {
+    let reporter = Reporter::new(session, format);
+    let parser = RecursiveDescentParser::from_lexer(Lexer::starting_at(session, start, line));
+
+    let program = match parser.clone().parse_program() {
+        Ok(program) => program,
+        Err(errors) => {
+            // A bare expression without its trailing ';' is also accepted.
+            let mut parser = parser;
+            match parser.parse() {
+                Ok(expr) if parser.is_at_end() => vec![Stmt::Expression(expr)],
+                _ => {
+                    reporter.report_all(&errors);
+                    return;
+                }
+            }
+        }
+    };
+
+    if let Err(e) = Resolver::new().resolve(&program) {
+        reporter.report(&e);
+        return;
+    }
+
+    // The value of an entry made of a single expression is echoed back.
+    let result = match program.as_slice() {
+        [Stmt::Expression(expr)] => interpreter.evaluate(expr).map(|value| println!("{value}")),
+        _ => interpreter.run(&program),
+    };
+
+    if let Err(e) = result {
+        reporter.report(&e);
+    }
+}
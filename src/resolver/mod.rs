@@ -0,0 +1,105 @@
+use std::fmt::Display;
+
+use crate::{
+    diagnostics::{Diagnostic, ToDiagnostic},
+    primitives::Span,
+};
+
+pub mod scope_resolver;
+
+pub use scope_resolver::Resolver;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    ReadInOwnInitializer { span: Span, name: String },
+    AlreadyDeclared { span: Span, name: String },
+    TopLevelReturn { span: Span },
+    ReturnFromInitializer { span: Span },
+    ThisOutsideClass { span: Span },
+    SuperOutsideClass { span: Span },
+    SuperWithoutSuperclass { span: Span },
+    InheritsFromItself { span: Span, name: String },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::ReadInOwnInitializer { span, name } => write!(
+                f,
+                "[line {}] Error at '{name}': Can't read local variable in its own initializer.",
+                span.line
+            ),
+            Error::AlreadyDeclared { span, name } => write!(
+                f,
+                "[line {}] Error at '{name}': Already a variable with this name in this scope.",
+                span.line
+            ),
+            Error::TopLevelReturn { span } => write!(
+                f,
+                "[line {}] Error at 'return': Can't return from top-level code.",
+                span.line
+            ),
+            Error::ReturnFromInitializer { span } => write!(
+                f,
+                "[line {}] Error at 'return': Can't return a value from an initializer.",
+                span.line
+            ),
+            Error::ThisOutsideClass { span } => write!(
+                f,
+                "[line {}] Error at 'this': Can't use 'this' outside of a class.",
+                span.line
+            ),
+            Error::SuperOutsideClass { span } => write!(
+                f,
+                "[line {}] Error at 'super': Can't use 'super' outside of a class.",
+                span.line
+            ),
+            Error::SuperWithoutSuperclass { span } => write!(
+                f,
+                "[line {}] Error at 'super': Can't use 'super' in a class with no superclass.",
+                span.line
+            ),
+            Error::InheritsFromItself { span, name } => write!(
+                f,
+                "[line {}] Error at '{name}': A class can't inherit from itself.",
+                span.line
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ToDiagnostic for Error {
+    fn diagnostic(&self) -> Diagnostic {
+        match self {
+            Error::ReadInOwnInitializer { span, .. } => {
+                Diagnostic::error("Can't read local variable in its own initializer.", *span)
+            }
+            Error::AlreadyDeclared { span, .. } => {
+                Diagnostic::error("Already a variable with this name in this scope.", *span)
+            }
+            Error::TopLevelReturn { span } => {
+                Diagnostic::error("Can't return from top-level code.", *span)
+            }
+            Error::ReturnFromInitializer { span } => {
+                Diagnostic::error("Can't return a value from an initializer.", *span)
+                    .with_note("'init' always returns 'this'")
+            }
+            Error::ThisOutsideClass { span } => {
+                Diagnostic::error("Can't use 'this' outside of a class.", *span)
+            }
+            Error::SuperOutsideClass { span } => {
+                Diagnostic::error("Can't use 'super' outside of a class.", *span)
+            }
+            Error::SuperWithoutSuperclass { span } => {
+                Diagnostic::error("Can't use 'super' in a class with no superclass.", *span)
+            }
+            Error::InheritsFromItself { span, .. } => {
+                Diagnostic::error("A class can't inherit from itself.", *span)
+            }
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use std::{cell::Cell, collections::HashMap};
+
+use crate::{
+    expr::{
+        expression::Expr,
+        statement::{ClassDecl, FunctionDecl, Stmt},
+    },
+    primitives::{Span, Symbol},
+};
+
+use super::{Error, Result};
+
+#[derive(Clone, Copy, PartialEq)]
+enum FunctionType {
+    None,
+    Function,
+    Method,
+    Initializer,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ClassType {
+    None,
+    Class,
+    Subclass,
+}
+
+/// Walks the AST before it is evaluated, binding every local variable
+/// reference to the scope it was declared in.
+pub struct Resolver {
+    /// Each scope maps a name to whether its initializer has finished.
+    scopes: Vec<HashMap<Symbol, bool>>,
+    current_function: FunctionType,
+    current_class: ClassType,
+    /// The interned `"this"`, `"init"` and `"super"`, checked in every class.
+    this_symbol: Symbol,
+    init_symbol: Symbol,
+    super_symbol: Symbol,
+}
+
+impl Resolver {
+    pub fn new() -> Self {
+        Self {
+            scopes: Vec::new(),
+            current_function: FunctionType::None,
+            current_class: ClassType::None,
+            this_symbol: Symbol::intern("this"),
+            init_symbol: Symbol::intern("init"),
+            super_symbol: Symbol::intern("super"),
+        }
+    }
+
+    pub fn resolve(&mut self, program: &[Stmt]) -> Result<()> {
+        program.iter().try_for_each(|stmt| self.resolve_stmt(stmt))
+    }
+
+    fn resolve_stmt(&mut self, stmt: &Stmt) -> Result<()> {
+        match stmt {
+            Stmt::Expression(expr) | Stmt::Print(expr) => self.resolve_expr(expr),
+            Stmt::Var {
+                name,
+                initializer,
+                span,
+            } => {
+                self.declare(name, *span)?;
+                if let Some(initializer) = initializer {
+                    self.resolve_expr(initializer)?;
+                }
+                self.define(name);
+
+                Ok(())
+            }
+            Stmt::Block(statements) => {
+                self.scopes.push(HashMap::new());
+                let result = self.resolve(statements);
+                self.scopes.pop();
+
+                result
+            }
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.resolve_expr(condition)?;
+                self.resolve_stmt(then_branch)?;
+                if let Some(else_branch) = else_branch {
+                    self.resolve_stmt(else_branch)?;
+                }
+
+                Ok(())
+            }
+            Stmt::While { condition, body } => {
+                self.resolve_expr(condition)?;
+                self.resolve_stmt(body)
+            }
+            Stmt::Function(declaration) => {
+                self.declare(&declaration.name, declaration.span)?;
+                self.define(&declaration.name);
+
+                self.resolve_function(declaration, FunctionType::Function)
+            }
+            Stmt::Class(declaration) => self.resolve_class(declaration),
+            Stmt::Return { value, span } => {
+                if self.current_function == FunctionType::None {
+                    return Err(Error::TopLevelReturn { span: *span });
+                }
+
+                match value {
+                    Some(_) if self.current_function == FunctionType::Initializer => {
+                        Err(Error::ReturnFromInitializer { span: *span })
+                    }
+                    Some(value) => self.resolve_expr(value),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+
+    fn resolve_expr(&mut self, expr: &Expr) -> Result<()> {
+        match expr {
+            Expr::Literal { .. } => Ok(()),
+            Expr::Interpolation { parts, .. } => {
+                parts.iter().try_for_each(|part| self.resolve_expr(part))
+            }
+            Expr::Grouping { expr, .. } | Expr::Unary { right: expr, .. } => {
+                self.resolve_expr(expr)
+            }
+            Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+                self.resolve_expr(left)?;
+                self.resolve_expr(right)
+            }
+            Expr::Call {
+                callee, arguments, ..
+            } => {
+                self.resolve_expr(callee)?;
+                arguments
+                    .iter()
+                    .try_for_each(|argument| self.resolve_expr(argument))
+            }
+            Expr::Variable { name, span, depth } => {
+                if let Some(scope) = self.scopes.last() {
+                    if scope.get(name) == Some(&false) {
+                        return Err(Error::ReadInOwnInitializer {
+                            span: *span,
+                            name: name.to_string(),
+                        });
+                    }
+                }
+
+                self.resolve_local(name, depth);
+                Ok(())
+            }
+            Expr::Get { object, .. } => self.resolve_expr(object),
+            Expr::Set { object, value, .. } => {
+                self.resolve_expr(value)?;
+                self.resolve_expr(object)
+            }
+            Expr::Assign {
+                name, value, depth, ..
+            } => {
+                self.resolve_expr(value)?;
+                self.resolve_local(name, depth);
+
+                Ok(())
+            }
+            Expr::This { span, depth } => {
+                if self.current_class == ClassType::None {
+                    return Err(Error::ThisOutsideClass { span: *span });
+                }
+
+                self.resolve_local(&self.this_symbol, depth);
+                Ok(())
+            }
+            Expr::Super { span, depth, .. } => match self.current_class {
+                ClassType::None => Err(Error::SuperOutsideClass { span: *span }),
+                ClassType::Class => Err(Error::SuperWithoutSuperclass { span: *span }),
+                ClassType::Subclass => {
+                    self.resolve_local(&self.super_symbol, depth);
+                    Ok(())
+                }
+            },
+        }
+    }
+
+    fn resolve_class(&mut self, declaration: &ClassDecl) -> Result<()> {
+        self.declare(&declaration.name, declaration.span)?;
+        self.define(&declaration.name);
+
+        let enclosing = self.current_class;
+        self.current_class = ClassType::Class;
+
+        if let Some(superclass) = &declaration.superclass {
+            if let Expr::Variable { name, span, .. } = superclass {
+                if *name == declaration.name {
+                    self.current_class = enclosing;
+                    return Err(Error::InheritsFromItself {
+                        span: *span,
+                        name: name.to_string(),
+                    });
+                }
+            }
+
+            self.current_class = ClassType::Subclass;
+            if let Err(e) = self.resolve_expr(superclass) {
+                self.current_class = enclosing;
+                return Err(e);
+            }
+
+            // Methods of a subclass close over a scope that defines `super`.
+            self.scopes
+                .push(HashMap::from([(self.super_symbol.clone(), true)]));
+        }
+
+        self.scopes
+            .push(HashMap::from([(self.this_symbol.clone(), true)]));
+
+        let result = declaration.methods.iter().try_for_each(|method| {
+            let kind = if method.name == self.init_symbol {
+                FunctionType::Initializer
+            } else {
+                FunctionType::Method
+            };
+
+            self.resolve_function(method, kind)
+        });
+
+        self.scopes.pop();
+        if declaration.superclass.is_some() {
+            self.scopes.pop();
+        }
+        self.current_class = enclosing;
+
+        result
+    }
+
+    fn resolve_function(&mut self, declaration: &FunctionDecl, kind: FunctionType) -> Result<()> {
+        let enclosing = std::mem::replace(&mut self.current_function, kind);
+        self.scopes.push(HashMap::new());
+
+        let result = declaration
+            .params
+            .iter()
+            .try_for_each(|param| {
+                self.declare(&param.name, param.span)?;
+                self.define(&param.name);
+                Ok(())
+            })
+            .and_then(|_| self.resolve(&declaration.body));
+
+        self.scopes.pop();
+        self.current_function = enclosing;
+
+        result
+    }
+
+    fn resolve_local(&self, name: &Symbol, depth: &Cell<Option<usize>>) {
+        let found = self
+            .scopes
+            .iter()
+            .rev()
+            .position(|scope| scope.contains_key(name));
+
+        depth.set(found);
+    }
+
+    fn declare(&mut self, name: &Symbol, span: Span) -> Result<()> {
+        let Some(scope) = self.scopes.last_mut() else {
+            return Ok(());
+        };
+
+        if scope.contains_key(name) {
+            return Err(Error::AlreadyDeclared {
+                span,
+                name: name.to_string(),
+            });
+        }
+
+        scope.insert(name.clone(), false);
+        Ok(())
+    }
+
+    fn define(&mut self, name: &Symbol) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.clone(), true);
+        }
+    }
+}
@@ -1,138 +1,478 @@
 use super::{Error, Result};
-use std::collections::HashMap;
 
-use crate::primitives::{Literal, Token, TokenType};
+use crate::primitives::{Literal, Span, Symbol, Token, TokenType};
 
+/// Splits source code into tokens. The cursor is a byte offset into `input`
+/// that only ever moves forward over whole characters, so every character
+/// is looked at a constant number of times and slices always fall on UTF-8
+/// boundaries.
 pub struct Lexer<'a> {
     pub input: &'a str,
+    /// Byte offset of the next character to read.
     pub index: usize,
     pub line: usize,
-    pub reserved_words: HashMap<&'a str, TokenType>,
+    /// Characters between the start of the current line and the cursor.
+    pub column: usize,
+    /// Byte offset where the token being scanned starts.
+    pub start: usize,
+    /// `column` at `start`.
+    pub start_column: usize,
+    /// Interpolations inside string literals that are still open, innermost
+    /// last.
+    pub interpolations: Vec<Interpolation>,
+    /// Set once the `EndOfFile` token has been produced.
+    pub finished: bool,
+    /// A malformed literal, produced right after its error.
+    pub pending: Option<Token<'a>>,
+}
+
+/// A `${` whose matching `}` has not been reached yet.
+pub struct Interpolation {
+    /// `{` opened inside the interpolated expression and not yet closed, so
+    /// that their `}` does not end the interpolation.
+    pub braces: usize,
+    /// Where the string literal containing it starts.
+    pub string: Span,
+    /// The `${` itself.
+    pub open: Span,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(input: &'a str) -> Self {
-        let reserved_words = HashMap::from([
-            ("and", TokenType::And),
-            ("class", TokenType::Class),
-            ("else", TokenType::Else),
-            ("false", TokenType::False),
-            ("for", TokenType::For),
-            ("fun", TokenType::Fun),
-            ("if", TokenType::If),
-            ("nil", TokenType::Nil),
-            ("or", TokenType::Or),
-            ("print", TokenType::Print),
-            ("return", TokenType::Return),
-            ("super", TokenType::Super),
-            ("this", TokenType::This),
-            ("true", TokenType::True),
-            ("var", TokenType::Var),
-            ("while", TokenType::While),
-        ]);
-
         Lexer {
             input,
             index: 0,
             line: 1,
-            reserved_words,
+            column: 0,
+            start: 0,
+            start_column: 0,
+            interpolations: Vec::new(),
+            finished: false,
+            pending: None,
+        }
+    }
+
+    /// Scans `input` from byte offset `index`, the start of line `line`. The
+    /// REPL keeps every entry in one text this way, so spans from earlier
+    /// entries still point at what they were scanned from.
+    pub fn starting_at(input: &'a str, index: usize, line: usize) -> Self {
+        Lexer {
+            index,
+            line,
+            ..Lexer::new(input)
         }
     }
 }
 
+/// The keyword spelled by `lexeme`, or `Identifier` if it is not one.
+fn keyword_or_identifier(lexeme: &str) -> TokenType {
+    match lexeme {
+        "and" => TokenType::And,
+        "class" => TokenType::Class,
+        "else" => TokenType::Else,
+        "false" => TokenType::False,
+        "for" => TokenType::For,
+        "fun" => TokenType::Fun,
+        "if" => TokenType::If,
+        "nil" => TokenType::Nil,
+        "or" => TokenType::Or,
+        "print" => TokenType::Print,
+        "return" => TokenType::Return,
+        "super" => TokenType::Super,
+        "this" => TokenType::This,
+        "true" => TokenType::True,
+        "var" => TokenType::Var,
+        "while" => TokenType::While,
+        _ => TokenType::Identifier,
+    }
+}
+
+/// Identifiers follow Unicode's XID_Start and XID_Continue properties, with
+/// `_` also allowed first.
+fn is_identifier_start(c: char) -> bool {
+    c == '_' || unicode_ident::is_xid_start(c)
+}
+
+fn is_identifier_continue(c: char) -> bool {
+    unicode_ident::is_xid_continue(c)
+}
+
 impl<'a> Lexer<'a> {
-    fn either(&mut self, target: char, is: TokenType, isnt: TokenType) -> TokenType {
-        match self.input.chars().nth(self.index) {
-            Some(c) if c == target => {
-                self.index += 1;
-                is
+    /// The character under the cursor.
+    fn peek(&self) -> Option<char> {
+        self.input[self.index..].chars().next()
+    }
+
+    /// The character after the one under the cursor.
+    fn peek_next(&self) -> Option<char> {
+        self.input[self.index..].chars().nth(1)
+    }
+
+    /// Moves the cursor past the next character and returns it.
+    fn advance(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.index += c.len_utf8();
+
+        if c == '\n' {
+            self.line += 1;
+            self.column = 0;
+        } else {
+            self.column += 1;
+        }
+
+        Some(c)
+    }
+
+    /// Advances while `predicate` holds for the character under the cursor.
+    fn advance_while(&mut self, predicate: impl Fn(char) -> bool) {
+        while self.peek().is_some_and(&predicate) {
+            self.advance();
+        }
+    }
+
+    /// Marks the cursor as the start of the next token.
+    fn begin_token(&mut self) {
+        self.start = self.index;
+        self.start_column = self.column;
+    }
+
+    /// A span from the start of the current token to the cursor. Columns
+    /// count characters, not bytes.
+    fn token_span(&self) -> Span {
+        Span::new(self.start, self.index, self.line, self.start_column + 1)
+    }
+
+    fn lexeme(&self) -> &'a str {
+        &self.input[self.start..self.index]
+    }
+
+    /// Produces `token`, or `error` when the literal it was scanned from is
+    /// malformed. The token still follows the error then, with a placeholder
+    /// value, so the parser does not report the literal as missing too.
+    fn literal(&mut self, token: Token<'a>, error: Option<Error>) -> Result<Token<'a>> {
+        match error {
+            Some(error) => {
+                self.pending = Some(token);
+                Err(error)
             }
-            _ => isnt,
+            None => Ok(token),
+        }
+    }
+
+    fn either(&mut self, target: char, is: TokenType, isnt: TokenType) -> TokenType {
+        if self.peek() == Some(target) {
+            self.advance();
+            is
+        } else {
+            isnt
         }
     }
 
-    fn string(&mut self, initial_index: usize) -> Option<Result<Token<'a>>> {
+    /// Scans up to the closing quote or the next `${`, even after an invalid
+    /// escape, so the rest of the string is not mistaken for code. Invalid
+    /// escapes are left out of the value.
+    ///
+    /// `"a ${x} b ${y} c"` becomes a `StringStart` for `"a ${`, the tokens of
+    /// `x`, a `StringMiddle` for `} b ${`, the tokens of `y` and a `StringEnd`
+    /// for `} c"`. `resumed` is where the literal starts when scanning
+    /// continues after an interpolation.
+    fn string(&mut self, resumed: Option<Span>) -> Result<Token<'a>> {
+        let start = self.token_span();
+        let string = resumed.unwrap_or(start);
+        let (segment, end) = match resumed {
+            None => (TokenType::StringStart, TokenType::String),
+            Some(_) => (TokenType::StringMiddle, TokenType::StringEnd),
+        };
+        let mut value = String::new();
+        let mut invalid = None;
+
         loop {
-            self.index += 1;
+            match self.advance() {
+                Some('$') if self.peek() == Some('{') => {
+                    self.advance();
+                    self.interpolations.push(Interpolation {
+                        braces: 0,
+                        string,
+                        open: Span::new(self.index - 2, self.index, self.line, self.column - 1),
+                    });
 
-            match self.input.chars().nth(self.index - 1) {
+                    let token = Token::new(
+                        segment,
+                        self.lexeme(),
+                        Literal::String(Symbol::intern(&value)),
+                        Span {
+                            end: self.index,
+                            ..start
+                        },
+                    );
+                    return self.literal(token, invalid);
+                }
                 Some('"') => {
-                    return Some(Ok(Token::new(
-                        TokenType::String,
-                        &self.input[initial_index..self.index],
-                        Literal::String(
-                            self.input[(initial_index + 1)..(self.index - 1)].to_string(),
-                        ),
-                        self.line,
-                    )))
+                    let token = Token::new(
+                        end,
+                        self.lexeme(),
+                        Literal::String(Symbol::intern(&value)),
+                        Span {
+                            end: self.index,
+                            ..start
+                        },
+                    );
+                    return self.literal(token, invalid);
                 }
-                Some('\n') => {
-                    self.line += 1;
+                Some('\\') => match self.escape() {
+                    Some(Ok(c)) => value.push(c),
+                    Some(Err(error)) => {
+                        invalid.get_or_insert(error);
+                    }
+                    None => continue,
+                },
+                Some(c) => value.push(c),
+                None if resumed.is_none() && !self.interpolations.is_empty() => {
+                    // In `"a ${b";` the quote was meant to end the string
+                    // and the `}` is missing: scan on from the quote as if
+                    // it closed the interpolation.
+                    self.index = start.end;
+                    self.line = start.line;
+                    self.column = start.column;
+
+                    let open = self.interpolations.pop().expect("An interpolation is open");
+                    let token = Token::new(
+                        TokenType::StringEnd,
+                        self.lexeme(),
+                        Literal::String(Symbol::intern("")),
+                        start,
+                    );
+                    return self.literal(
+                        token,
+                        Some(Error::UnclosedInterpolation { span: open.open }),
+                    );
                 }
-                Some(_) => continue,
                 None => {
-                    self.index -= 1;
-                    return Some(Err(Error::UnterminatedString { line: self.line }));
+                    // Any interpolation still open is part of this string.
+                    self.interpolations.clear();
+
+                    return Err(Error::UnterminatedString {
+                        span: Span {
+                            end: self.index,
+                            ..string
+                        },
+                        line: self.line,
+                    });
                 }
-            };
+            }
         }
     }
 
-    fn number(&mut self, initial_index: usize) -> Token<'a> {
-        let mut has_dot = false;
-        let gen_token = |i: usize| {
-            let slice = &self.input[initial_index..i];
+    /// Reads the escape sequence after a `\`. `None` when the source ends
+    /// first, which the caller reports as an unterminated string.
+    fn escape(&mut self) -> Option<Result<char>> {
+        // The backslash was just consumed.
+        let start = self.index - 1;
+        let (line, column) = (self.line, self.column);
 
-            let number = slice
-                .parse::<f64>()
-                .expect("We know everything before is a number");
+        let span = |lexer: &Self| Span::new(start, lexer.index, line, column);
 
-            return Token::new(TokenType::Number, slice, Literal::Number(number), self.line);
+        let escaped = match self.advance()? {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '\\' => '\\',
+            '"' => '"',
+            '$' => '$',
+            '0' => '\0',
+            'u' => {
+                return Some(
+                    self.unicode_escape()
+                        .ok_or_else(|| Error::InvalidUnicodeEscape { span: span(self) }),
+                )
+            }
+            which => {
+                return Some(Err(Error::InvalidEscape {
+                    span: span(self),
+                    which,
+                }))
+            }
         };
 
-        loop {
-            self.index += 1;
-            match self.input.chars().nth(self.index - 1) {
-                Some('.') => {
-                    if !has_dot {
-                        has_dot = true;
-                    } else {
-                        self.index -= 1;
-                        return gen_token(self.index);
-                    }
-                }
-                Some(c) if c.is_numeric() => continue,
-                Some(_) => {
-                    self.index -= 1;
-                    return gen_token(self.index);
-                }
-                None => {
-                    self.index -= 1;
-                    return gen_token(self.index);
-                }
+        Some(Ok(escaped))
+    }
+
+    /// Reads the `{XXXX}` of a `\u{XXXX}` escape: one to six hex digits naming
+    /// a Unicode scalar value. Stops before anything unexpected so a closing
+    /// quote still ends the string.
+    fn unicode_escape(&mut self) -> Option<char> {
+        if self.peek() != Some('{') {
+            return None;
+        }
+        self.advance();
+
+        let digits_start = self.index;
+        self.advance_while(|c| c.is_ascii_hexdigit());
+        let digits = &self.input[digits_start..self.index];
+
+        if self.peek() != Some('}') {
+            return None;
+        }
+        self.advance();
+
+        if digits.is_empty() || digits.len() > 6 {
+            return None;
+        }
+
+        u32::from_str_radix(digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+    }
+
+    /// Scans a `0x` hex or `0b` binary integer, or a decimal with an optional
+    /// fraction and exponent. `_` may separate two digits. A `.` right after
+    /// the number that does not start its fraction, as in `1.`, `1.5.` or
+    /// `0xF.8`, makes the whole literal invalid.
+    fn number(&mut self, first: char) -> Result<Token<'a>> {
+        let (number, no_fraction) = match (first, self.peek()) {
+            ('0', Some('x' | 'X')) => {
+                self.advance();
+                (
+                    self.radix_integer(16, "`0x` must be followed by hex digits 0-9 and a-f"),
+                    "hex numbers cannot have a fraction",
+                )
+            }
+            ('0', Some('b' | 'B')) => {
+                self.advance();
+                (
+                    self.radix_integer(2, "`0b` must be followed by binary digits 0 and 1"),
+                    "binary numbers cannot have a fraction",
+                )
+            }
+            _ => (self.decimal(), "a number can only have one `.`"),
+        };
+
+        let number = number.and_then(|number| {
+            if self.peek() != Some('.') {
+                return Ok(number);
             }
+
+            self.advance();
+            let reason = if self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                no_fraction
+            } else {
+                "a `.` in a number must be followed by digits"
+            };
+            self.advance_while(|c| c.is_ascii_digit() || c == '_');
+
+            Err(self.invalid_number(reason))
+        });
+
+        let (number, error) = match number {
+            Ok(number) => (number, None),
+            Err(error) => (0.0, Some(error)),
+        };
+        let token = Token::new(
+            TokenType::Number,
+            self.lexeme(),
+            Literal::Number(number),
+            self.token_span(),
+        );
+
+        self.literal(token, error)
+    }
+
+    /// Reads the digits after a `0x` or `0b` prefix. Letters and digits
+    /// outside the radix are read too, so `0b102` is reported as a whole
+    /// rather than as `0b10` followed by `2`.
+    fn radix_integer(&mut self, radix: u32, expected: &'static str) -> Result<f64> {
+        let digits_start = self.index;
+        self.advance_while(|c| c.is_ascii_alphanumeric() || c == '_');
+        let digits = &self.input[digits_start..self.index];
+
+        if digits.is_empty() || digits.chars().any(|c| c != '_' && !c.is_digit(radix)) {
+            return Err(self.invalid_number(expected));
         }
+        self.check_separators(|c| c.is_digit(radix))?;
+
+        // Integers beyond 2^53 are rounded, as they would be in a decimal.
+        let number = digits
+            .chars()
+            .filter_map(|c| c.to_digit(radix))
+            .fold(0.0, |number, digit| {
+                number * f64::from(radix) + f64::from(digit)
+            });
+
+        self.finite(Some(number))
     }
 
-    fn identifier(&mut self, initial_index: usize) -> Token<'a> {
-        loop {
-            self.index += 1;
+    /// Reads the rest of a decimal after its first digit.
+    fn decimal(&mut self) -> Result<f64> {
+        let is_digit = |c: char| c.is_ascii_digit() || c == '_';
 
-            match self.input.chars().nth(self.index - 1) {
-                Some(c) if c.is_alphanumeric() || c == '_' => continue,
-                _ => break,
+        self.advance_while(is_digit);
+
+        if self.peek() == Some('.') && self.peek_next().is_some_and(|c| c.is_ascii_digit()) {
+            self.advance();
+            self.advance_while(is_digit);
+        }
+
+        if let Some('e' | 'E') = self.peek() {
+            self.advance();
+            if let Some('+' | '-') = self.peek() {
+                self.advance();
+            }
+
+            if !self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                return Err(self.invalid_number("the exponent needs at least one digit"));
             }
+            self.advance_while(is_digit);
+        }
+
+        self.check_separators(|c| c.is_ascii_digit())?;
+
+        self.finite(self.lexeme().replace('_', "").parse::<f64>().ok())
+    }
+
+    /// Rejects numbers too large for a double, which would otherwise become
+    /// infinity.
+    fn finite(&self, number: Option<f64>) -> Result<f64> {
+        number
+            .filter(|number| number.is_finite())
+            .ok_or_else(|| self.invalid_number("the number is too large to represent"))
+    }
+
+    /// Every `_` in the number being scanned must sit between two digits.
+    fn check_separators(&self, is_digit: impl Fn(char) -> bool) -> Result<()> {
+        let lexeme = self.lexeme();
+
+        let misplaced = lexeme.char_indices().any(|(i, c)| {
+            c == '_'
+                && !(lexeme[..i].chars().next_back().is_some_and(&is_digit)
+                    && lexeme[i + 1..].chars().next().is_some_and(&is_digit))
+        });
+
+        if misplaced {
+            return Err(self.invalid_number("`_` can only be used between two digits"));
         }
 
-        self.index -= 1;
-        let slice = &self.input[initial_index..self.index];
+        Ok(())
+    }
 
-        let token_type = self
-            .reserved_words
-            .get(slice)
-            .unwrap_or(&TokenType::Identifier);
+    fn invalid_number(&self, reason: &'static str) -> Error {
+        Error::InvalidNumber {
+            span: self.token_span(),
+            literal: self.lexeme().to_string(),
+            reason,
+        }
+    }
 
-        Token::new(*token_type, slice, Literal::Null, self.line)
+    fn identifier(&mut self) -> Token<'a> {
+        self.advance_while(is_identifier_continue);
+
+        let slice = self.lexeme();
+
+        Token::new(
+            keyword_or_identifier(slice),
+            slice,
+            Literal::Null,
+            self.token_span(),
+        )
     }
 }
 
@@ -140,82 +480,96 @@ impl<'a> Iterator for Lexer<'a> {
     type Item = Result<Token<'a>>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let len = self.input.chars().count();
+        if let Some(token) = self.pending.take() {
+            return Some(Ok(token));
+        }
 
         loop {
-            if let Some(c) = self.input.chars().nth(self.index) {
-                let initial_index = self.index;
-                self.index += 1;
+            self.begin_token();
 
-                if c.is_whitespace() {
-                    self.line += (c == '\n') as usize;
-                    continue;
+            let Some(c) = self.advance() else {
+                if self.finished {
+                    return None;
                 }
 
-                let token_type: TokenType = match c {
-                    '(' => TokenType::LeftParenthesis,
-                    ')' => TokenType::RightParenthesis,
-                    '{' => TokenType::LeftBracket,
-                    '}' => TokenType::RightBracket,
-                    '*' => TokenType::Asterisk,
-                    '.' => TokenType::Dot,
-                    ',' => TokenType::Comma,
-                    '+' => TokenType::Plus,
-                    '-' => TokenType::Minus,
-                    ';' => TokenType::SemiColon,
-                    '!' => self.either('=', TokenType::BangEqual, TokenType::Bang),
-                    '=' => self.either('=', TokenType::EqualEqual, TokenType::Equal),
-                    '>' => self.either('=', TokenType::GreaterEqual, TokenType::Greater),
-                    '<' => self.either('=', TokenType::LessEqual, TokenType::Less),
-                    '/' => match self.input.chars().nth(self.index) {
-                        Some('/') => {
-                            loop {
-                                self.index += 1;
-                                match self.input.chars().nth(self.index) {
-                                    Some('\n') => break,
-                                    None => break,
-                                    _ => continue,
-                                }
-                            }
-                            continue;
-                        }
-                        _ => TokenType::Slash,
-                    },
-                    '"' => return self.string(initial_index),
-                    c if c.is_numeric() => return Some(Ok(self.number(initial_index))),
-                    c if c.is_alphabetic() || c == '_' => {
-                        return Some(Ok(self.identifier(initial_index)))
-                    }
-                    c => {
-                        return Some(Err(Error::UnrecognizedCharacter {
-                            line: self.line,
-                            which: c,
-                        }))
-                    }
-                };
+                // The source ended inside `${...}`: report the string once.
+                if let Some(open) = self.interpolations.first() {
+                    let span = Span {
+                        end: self.index,
+                        ..open.string
+                    };
+                    self.interpolations.clear();
+
+                    return Some(Err(Error::UnterminatedString {
+                        span,
+                        line: self.line,
+                    }));
+                }
+
+                self.finished = true;
 
-                let token = Token::new(
-                    token_type,
-                    &self.input[initial_index..self.index],
+                return Some(Ok(Token::new(
+                    TokenType::EndOfFile,
+                    "",
                     Literal::Null,
-                    self.line,
-                );
+                    self.token_span(),
+                )));
+            };
 
-                return Some(Ok(token));
-            } else {
-                if self.index == len {
-                    self.index += 1;
-
-                    return Some(Ok(Token::new(
-                        TokenType::EndOfFile,
-                        "",
-                        Literal::Null,
-                        self.line,
-                    )));
+            if c.is_whitespace() {
+                continue;
+            }
+
+            let token_type: TokenType = match c {
+                '(' => TokenType::LeftParenthesis,
+                ')' => TokenType::RightParenthesis,
+                '{' => {
+                    if let Some(open) = self.interpolations.last_mut() {
+                        open.braces += 1;
+                    }
+                    TokenType::LeftBracket
                 }
+                '}' => match self.interpolations.last_mut() {
+                    Some(open) if open.braces == 0 => {
+                        let string = open.string;
+                        self.interpolations.pop();
+                        return Some(self.string(Some(string)));
+                    }
+                    Some(open) => {
+                        open.braces -= 1;
+                        TokenType::RightBracket
+                    }
+                    None => TokenType::RightBracket,
+                },
+                '*' => TokenType::Asterisk,
+                '.' => TokenType::Dot,
+                ',' => TokenType::Comma,
+                '+' => TokenType::Plus,
+                '-' => TokenType::Minus,
+                ';' => TokenType::SemiColon,
+                '!' => self.either('=', TokenType::BangEqual, TokenType::Bang),
+                '=' => self.either('=', TokenType::EqualEqual, TokenType::Equal),
+                '>' => self.either('=', TokenType::GreaterEqual, TokenType::Greater),
+                '<' => self.either('=', TokenType::LessEqual, TokenType::Less),
+                '/' if self.peek() == Some('/') => {
+                    self.advance_while(|c| c != '\n');
+                    continue;
+                }
+                '/' => TokenType::Slash,
+                '"' => return Some(self.string(None)),
+                c if c.is_ascii_digit() => return Some(self.number(c)),
+                c if is_identifier_start(c) => return Some(Ok(self.identifier())),
+                c => {
+                    return Some(Err(Error::UnrecognizedCharacter {
+                        span: self.token_span(),
+                        which: c,
+                    }))
+                }
+            };
 
-                return None;
-            }
+            let token = Token::new(token_type, self.lexeme(), Literal::Null, self.token_span());
+
+            return Some(Ok(token));
         }
     }
 }
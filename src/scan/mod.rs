@@ -1,26 +1,141 @@
 use std::fmt::Display;
 
+use crate::{
+    diagnostics::{Diagnostic, ToDiagnostic},
+    primitives::Span,
+};
+
 pub mod lexer;
 
 pub type Result<T> = core::result::Result<T, Error>;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Error {
-    UnrecognizedCharacter { line: usize, which: char },
-    UnterminatedString { line: usize },
+    UnrecognizedCharacter {
+        span: Span,
+        which: char,
+    },
+    /// `span` covers the whole unterminated string, `line` is where the
+    /// source ended.
+    UnterminatedString {
+        span: Span,
+        line: usize,
+    },
+    /// `which` is the character after the backslash.
+    InvalidEscape {
+        span: Span,
+        which: char,
+    },
+    /// A `\u` not followed by `{`, one to six hex digits, `}`, or naming
+    /// something that is not a Unicode scalar value.
+    InvalidUnicodeEscape {
+        span: Span,
+    },
+    /// A `${` with no `}`, found when the quote meant to end its string
+    /// starts a new one that runs to the end of the source instead.
+    UnclosedInterpolation {
+        span: Span,
+    },
+    /// `literal` is the malformed number as written, `reason` says what is
+    /// wrong with it.
+    InvalidNumber {
+        span: Span,
+        literal: String,
+        reason: &'static str,
+    },
+}
+
+impl Error {
+    pub fn span(&self) -> Span {
+        match self {
+            Error::UnrecognizedCharacter { span, .. }
+            | Error::UnterminatedString { span, .. }
+            | Error::InvalidEscape { span, .. }
+            | Error::InvalidUnicodeEscape { span }
+            | Error::UnclosedInterpolation { span }
+            | Error::InvalidNumber { span, .. } => *span,
+        }
+    }
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Error::UnrecognizedCharacter { line, which } => {
-                write!(f, "[line {line}] Error: Unexpected character: {which}")
+            Error::UnrecognizedCharacter { span, which } => {
+                write!(
+                    f,
+                    "[line {}] Error: Unexpected character: {which}",
+                    span.line
+                )
             }
-            Error::UnterminatedString { line } => {
+            Error::UnterminatedString { line, .. } => {
                 write!(f, "[line {line}] Error: Unterminated string.")
             }
+            Error::InvalidEscape { span, which } => {
+                write!(
+                    f,
+                    "[line {}] Error: Invalid escape sequence: \\{}",
+                    span.line,
+                    which.escape_debug()
+                )
+            }
+            Error::InvalidUnicodeEscape { span } => {
+                write!(
+                    f,
+                    "[line {}] Error: Invalid Unicode escape sequence.",
+                    span.line
+                )
+            }
+            Error::UnclosedInterpolation { span } => {
+                write!(
+                    f,
+                    "[line {}] Error: Missing '}}' to close '${{'.",
+                    span.line
+                )
+            }
+            Error::InvalidNumber { span, literal, .. } => {
+                write!(
+                    f,
+                    "[line {}] Error: Invalid number literal: {literal}",
+                    span.line
+                )
+            }
         }
     }
 }
 
 impl std::error::Error for Error {}
+
+impl ToDiagnostic for Error {
+    fn diagnostic(&self) -> Diagnostic {
+        match self {
+            Error::UnrecognizedCharacter { span, which } => {
+                Diagnostic::error(format!("Unexpected character: {which}"), *span)
+            }
+            Error::UnterminatedString { span, .. } => {
+                Diagnostic::error("Unterminated string.", *span)
+                    .with_note("strings must be closed with '\"' before the end of the file")
+            }
+            Error::InvalidEscape { span, which } => Diagnostic::error(
+                format!("Invalid escape sequence: \\{}", which.escape_debug()),
+                *span,
+            )
+            .with_note("the supported escapes are \\n \\t \\r \\\\ \\\" \\$ \\0 and \\u{XXXX}"),
+            Error::InvalidUnicodeEscape { span } => {
+                Diagnostic::error("Invalid Unicode escape sequence.", *span)
+                    .with_note("write the code point as \\u{XXXX}, with one to six hex digits")
+            }
+            Error::UnclosedInterpolation { span } => {
+                Diagnostic::error("Missing '}' to close '${'.", *span).with_note(
+                    "the interpolated expression must end with '}' before the string does",
+                )
+            }
+            Error::InvalidNumber {
+                span,
+                literal,
+                reason,
+            } => Diagnostic::error(format!("Invalid number literal: {literal}"), *span)
+                .with_note(*reason),
+        }
+    }
+}
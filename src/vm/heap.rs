@@ -0,0 +1,282 @@
+use std::{collections::HashMap, fmt::Display, rc::Rc};
+
+use super::{
+    object::{BoundMethod, Class, Closure, Function, Instance, Object, Upvalue},
+    Value,
+};
+
+/// A handle to an object on the `Heap`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObjRef(u32);
+
+/// The heap is not collected before it holds this many bytes.
+const FIRST_COLLECTION: usize = 1024 * 1024;
+
+/// After a collection, the next one happens once the heap has grown by this
+/// factor.
+const GROWTH_FACTOR: usize = 2;
+
+/// Owns every object the VM creates. Objects stay alive until a collection
+/// finds them unreachable from the roots the VM marks; their slots are then
+/// reused by later allocations.
+pub struct Heap {
+    objects: Vec<Option<Object>>,
+    marks: Vec<bool>,
+    /// Slots freed by the last collections.
+    free: Vec<u32>,
+    /// Marked objects whose references have not been marked yet.
+    gray: Vec<ObjRef>,
+    /// Every string on the heap, so equal strings share one object. The
+    /// table does not keep them alive: strings nothing else marks are
+    /// dropped from it before the sweep.
+    strings: HashMap<Rc<str>, ObjRef>,
+    bytes_allocated: usize,
+    next_collection: usize,
+}
+
+/// What a collection freed.
+pub struct Collection {
+    pub objects: usize,
+    pub bytes_before: usize,
+    pub bytes_after: usize,
+    pub next_collection: usize,
+}
+
+impl Default for Heap {
+    fn default() -> Self {
+        Self {
+            objects: Vec::new(),
+            marks: Vec::new(),
+            free: Vec::new(),
+            gray: Vec::new(),
+            strings: HashMap::new(),
+            bytes_allocated: 0,
+            next_collection: FIRST_COLLECTION,
+        }
+    }
+}
+
+impl Heap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores `object` without collecting. Callers that hold handles outside
+    /// of the VM roots, like the compiler, rely on this.
+    pub fn alloc(&mut self, object: Object) -> ObjRef {
+        self.bytes_allocated += object.size();
+
+        if let Some(index) = self.free.pop() {
+            self.objects[index as usize] = Some(object);
+            return ObjRef(index);
+        }
+
+        let index = u32::try_from(self.objects.len()).expect("The heap has room for the object");
+        self.objects.push(Some(object));
+        self.marks.push(false);
+
+        ObjRef(index)
+    }
+
+    /// Returns the string object holding `string`, allocating it the first
+    /// time. Like `alloc`, this never collects.
+    pub fn intern(&mut self, string: &str) -> ObjRef {
+        if let Some(&handle) = self.strings.get(string) {
+            return handle;
+        }
+
+        let string: Rc<str> = Rc::from(string);
+        let handle = self.alloc(Object::String(Rc::clone(&string)));
+        self.strings.insert(string, handle);
+
+        handle
+    }
+
+    pub fn get(&self, handle: ObjRef) -> &Object {
+        self.objects[handle.0 as usize]
+            .as_ref()
+            .expect("The handle points to a live object")
+    }
+
+    pub fn get_mut(&mut self, handle: ObjRef) -> &mut Object {
+        self.objects[handle.0 as usize]
+            .as_mut()
+            .expect("The handle points to a live object")
+    }
+
+    /// Changes the object behind `handle` in place and counts the memory it
+    /// gains or gives back, like an instance getting a new field.
+    pub fn update<T>(&mut self, handle: ObjRef, change: impl FnOnce(&mut Object) -> T) -> T {
+        let object = self.objects[handle.0 as usize]
+            .as_mut()
+            .expect("The handle points to a live object");
+
+        let before = object.size();
+        let result = change(object);
+        self.bytes_allocated = self.bytes_allocated - before + object.size();
+
+        result
+    }
+
+    pub fn should_collect(&self) -> bool {
+        self.bytes_allocated > self.next_collection
+    }
+
+    pub fn mark_value(&mut self, value: Value) {
+        if let Value::Object(handle) = value {
+            self.mark_object(handle);
+        }
+    }
+
+    pub fn mark_object(&mut self, handle: ObjRef) {
+        let mark = &mut self.marks[handle.0 as usize];
+        if !*mark {
+            *mark = true;
+            self.gray.push(handle);
+        }
+    }
+
+    /// Marks everything reachable from the roots marked so far, frees every
+    /// object left unmarked and clears the marks for the next collection.
+    pub fn collect(&mut self) -> Collection {
+        while let Some(handle) = self.gray.pop() {
+            let Self {
+                objects,
+                marks,
+                gray,
+                ..
+            } = self;
+
+            let object = objects[handle.0 as usize]
+                .as_ref()
+                .expect("Only live objects are marked");
+            object.references(|value| {
+                if let Value::Object(reference) = value {
+                    let mark = &mut marks[reference.0 as usize];
+                    if !*mark {
+                        *mark = true;
+                        gray.push(reference);
+                    }
+                }
+            });
+        }
+
+        let marks = &self.marks;
+        self.strings.retain(|_, handle| marks[handle.0 as usize]);
+
+        let bytes_before = self.bytes_allocated;
+        let mut freed = 0;
+        let mut bytes_after = 0;
+
+        for (index, (slot, mark)) in self.objects.iter_mut().zip(&mut self.marks).enumerate() {
+            match slot {
+                Some(object) if *mark => bytes_after += object.size(),
+                Some(_) => {
+                    *slot = None;
+                    self.free.push(index as u32);
+                    freed += 1;
+                }
+                None => {}
+            }
+            *mark = false;
+        }
+
+        self.bytes_allocated = bytes_after;
+        self.next_collection = (bytes_after * GROWTH_FACTOR).max(FIRST_COLLECTION);
+
+        Collection {
+            objects: freed,
+            bytes_before,
+            bytes_after,
+            next_collection: self.next_collection,
+        }
+    }
+
+    pub fn string(&self, handle: ObjRef) -> &str {
+        match self.get(handle) {
+            Object::String(string) => string,
+            _ => unreachable!("The handle points to a string"),
+        }
+    }
+
+    pub fn function(&self, handle: ObjRef) -> &Function {
+        match self.get(handle) {
+            Object::Function(function) => function,
+            _ => unreachable!("The handle points to a function"),
+        }
+    }
+
+    pub fn closure(&self, handle: ObjRef) -> &Closure {
+        match self.get(handle) {
+            Object::Closure(closure) => closure,
+            _ => unreachable!("The handle points to a closure"),
+        }
+    }
+
+    pub fn upvalue(&self, handle: ObjRef) -> &Upvalue {
+        match self.get(handle) {
+            Object::Upvalue(upvalue) => upvalue,
+            _ => unreachable!("The handle points to an upvalue"),
+        }
+    }
+
+    pub fn upvalue_mut(&mut self, handle: ObjRef) -> &mut Upvalue {
+        match self.get_mut(handle) {
+            Object::Upvalue(upvalue) => upvalue,
+            _ => unreachable!("The handle points to an upvalue"),
+        }
+    }
+
+    pub fn class(&self, handle: ObjRef) -> &Class {
+        match self.get(handle) {
+            Object::Class(class) => class,
+            _ => unreachable!("The handle points to a class"),
+        }
+    }
+
+    /// Formats `value` the way `print` shows it.
+    pub fn display(&self, value: Value) -> DisplayValue<'_> {
+        DisplayValue { heap: self, value }
+    }
+}
+
+pub struct DisplayValue<'h> {
+    heap: &'h Heap,
+    value: Value,
+}
+
+impl DisplayValue<'_> {
+    fn function(&self, f: &mut std::fmt::Formatter<'_>, function: &Function) -> std::fmt::Result {
+        match &function.name {
+            Some(name) => write!(f, "<fn {name}>"),
+            None => write!(f, "<script>"),
+        }
+    }
+}
+
+impl Display for DisplayValue<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let heap = self.heap;
+
+        match self.value {
+            Value::Nil => write!(f, "nil"),
+            Value::Bool(b) => write!(f, "{b}"),
+            Value::Number(n) => write!(f, "{n}"),
+            Value::Object(handle) => match heap.get(handle) {
+                Object::String(string) => write!(f, "{string}"),
+                Object::Function(function) => self.function(f, function),
+                Object::Native(native) => write!(f, "{native}"),
+                Object::Closure(closure) => self.function(f, heap.function(closure.function)),
+                Object::Upvalue(_) => write!(f, "upvalue"),
+                Object::Class(class) => write!(f, "<class {}>", class.name),
+                Object::Instance(Instance { class, .. }) => {
+                    write!(f, "{} instance", heap.class(*class).name)
+                }
+                Object::BoundMethod(BoundMethod { method, .. }) => {
+                    let closure = heap.closure(*method);
+                    self.function(f, heap.function(closure.function))
+                }
+            },
+        }
+    }
+}
@@ -0,0 +1,779 @@
+use std::{collections::HashMap, fmt::Write, rc::Rc};
+
+use crate::{
+    chunk::Chunk,
+    chunk::OpCode,
+    evaluate::TraceLine,
+    native::{self, Native, NativeValue},
+};
+
+use super::{
+    native::convert_arguments,
+    object::{BoundMethod, Class, Closure, Instance, Object, Upvalue},
+    Error, Heap, ObjRef, Result, RuntimeError, Value,
+};
+
+/// Calls nested deeper than this are reported as a stack overflow.
+const FRAMES_MAX: usize = 1024;
+
+/// A call to a closure that has not returned yet.
+struct CallFrame {
+    closure: ObjRef,
+    chunk: Rc<Chunk>,
+    ip: usize,
+    /// Where the callee sits on the stack, followed by its arguments and
+    /// locals.
+    slots: usize,
+}
+
+pub struct Vm {
+    heap: Heap,
+    stack: Vec<Value>,
+    frames: Vec<CallFrame>,
+    /// Keyed by the interned variable name.
+    globals: HashMap<ObjRef, Value>,
+    /// Upvalues still pointing into the stack, to be closed when the slot
+    /// they point to goes out of scope.
+    open_upvalues: Vec<ObjRef>,
+    /// The interned `"init"`, looked up on every class call.
+    init_string: ObjRef,
+    /// Print the stack and the next instruction before executing it.
+    trace: bool,
+    /// Collect garbage on every allocation instead of when the heap grows.
+    gc_stress: bool,
+    /// Report what every collection freed.
+    gc_log: bool,
+}
+
+impl Vm {
+    pub fn new() -> Self {
+        let mut heap = Heap::new();
+        let init_string = heap.intern("init");
+
+        let mut vm = Self {
+            heap,
+            stack: Vec::new(),
+            frames: Vec::new(),
+            globals: HashMap::new(),
+            open_upvalues: Vec::new(),
+            init_string,
+            trace: false,
+            gc_stress: false,
+            gc_log: false,
+        };
+
+        for native in native::standard() {
+            vm.define_native(native);
+        }
+
+        vm
+    }
+
+    /// The compiler allocates constants directly on the VM heap.
+    pub fn heap_mut(&mut self) -> &mut Heap {
+        &mut self.heap
+    }
+
+    pub fn set_trace(&mut self, trace: bool) {
+        self.trace = trace;
+    }
+
+    pub fn set_gc_stress(&mut self, gc_stress: bool) {
+        self.gc_stress = gc_stress;
+    }
+
+    pub fn set_gc_log(&mut self, gc_log: bool) {
+        self.gc_log = gc_log;
+    }
+
+    /// Installs a Rust function as a global that scripts can call like any
+    /// other Lox function.
+    pub fn define_native(&mut self, native: Native) {
+        let name = native.name.clone();
+        let native = self.alloc(Object::Native(native));
+
+        // Interning may collect, the native is only reachable from the stack.
+        self.push(Value::Object(native));
+        let name = self.intern(&name);
+        self.pop();
+
+        self.globals.insert(name, Value::Object(native));
+    }
+
+    /// Runs the compiled top level script.
+    pub fn interpret(&mut self, script: ObjRef) -> core::result::Result<(), RuntimeError> {
+        let closure = self.alloc(Object::Closure(Closure {
+            function: script,
+            upvalues: Vec::new(),
+        }));
+        self.push(Value::Object(closure));
+
+        let result = self.call(closure, 0).and_then(|()| self.run());
+
+        result.map_err(|error| self.runtime_error(error))
+    }
+
+    fn runtime_error(&mut self, error: Error) -> RuntimeError {
+        let span = self.frames.last().map_or_else(Default::default, |frame| {
+            frame.chunk.spans[frame.ip.saturating_sub(1)]
+        });
+
+        let trace = self
+            .frames
+            .iter()
+            .rev()
+            .map(|frame| {
+                let function = self.heap.closure(frame.closure).function;
+                TraceLine {
+                    line: frame.chunk.spans[frame.ip.saturating_sub(1)].line,
+                    function: self.heap.function(function).name.clone(),
+                }
+            })
+            .collect();
+
+        self.stack.clear();
+        self.frames.clear();
+        self.open_upvalues.clear();
+
+        RuntimeError { error, span, trace }
+    }
+
+    /// Allocates `object`, collecting garbage first if the heap has grown
+    /// enough. The new object is kept alive even if nothing points to it
+    /// yet.
+    fn alloc(&mut self, object: Object) -> ObjRef {
+        let handle = self.heap.alloc(object);
+
+        if self.gc_stress || self.heap.should_collect() {
+            self.heap.mark_object(handle);
+            self.collect_garbage();
+        }
+
+        handle
+    }
+
+    /// Like `alloc`, for a string that may already be interned.
+    fn intern(&mut self, string: &str) -> ObjRef {
+        let handle = self.heap.intern(string);
+
+        if self.gc_stress || self.heap.should_collect() {
+            self.heap.mark_object(handle);
+            self.collect_garbage();
+        }
+
+        handle
+    }
+
+    /// Changes an object that may grow, collecting garbage afterwards if the
+    /// heap has grown enough. The object must be reachable from the roots.
+    fn update<T>(&mut self, handle: ObjRef, change: impl FnOnce(&mut Object) -> T) -> T {
+        let result = self.heap.update(handle, change);
+
+        if self.gc_stress || self.heap.should_collect() {
+            self.collect_garbage();
+        }
+
+        result
+    }
+
+    /// The roots are the stack, the globals, the running closures and the
+    /// upvalues still pointing into the stack.
+    fn collect_garbage(&mut self) {
+        for &value in &self.stack {
+            self.heap.mark_value(value);
+        }
+        for (&name, &value) in &self.globals {
+            self.heap.mark_object(name);
+            self.heap.mark_value(value);
+        }
+        self.heap.mark_object(self.init_string);
+        for frame in &self.frames {
+            self.heap.mark_object(frame.closure);
+        }
+        for &upvalue in &self.open_upvalues {
+            self.heap.mark_object(upvalue);
+        }
+
+        let collection = self.heap.collect();
+
+        if self.gc_log {
+            eprintln!(
+                "-- gc: freed {} bytes in {} objects ({} -> {}), next at {}",
+                collection.bytes_before - collection.bytes_after,
+                collection.objects,
+                collection.bytes_before,
+                collection.bytes_after,
+                collection.next_collection,
+            );
+        }
+    }
+
+    /// Writes the stack and the instruction about to run to stderr, keeping
+    /// stdout for what the script prints.
+    fn trace_instruction(&self) {
+        let mut out = String::from("          ");
+        for value in &self.stack {
+            out.push_str(&format!("[ {} ]", self.heap.display(*value)));
+        }
+        out.push('\n');
+
+        let frame = self.frame();
+        frame
+            .chunk
+            .disassemble_instruction(frame.ip, &self.heap, &mut out);
+
+        eprint!("{out}");
+    }
+
+    fn push(&mut self, value: Value) {
+        self.stack.push(value);
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().expect("The stack is not empty")
+    }
+
+    fn peek(&self, distance: usize) -> Value {
+        self.stack[self.stack.len() - 1 - distance]
+    }
+
+    fn frame(&self) -> &CallFrame {
+        self.frames.last().expect("There is always a frame running")
+    }
+
+    fn frame_mut(&mut self) -> &mut CallFrame {
+        self.frames
+            .last_mut()
+            .expect("There is always a frame running")
+    }
+
+    fn read_byte(&mut self) -> u8 {
+        let frame = self.frame_mut();
+        let byte = frame.chunk.code[frame.ip];
+        frame.ip += 1;
+
+        byte
+    }
+
+    fn read_u16(&mut self) -> u16 {
+        let frame = self.frame_mut();
+        let value = frame.chunk.read_u16(frame.ip);
+        frame.ip += 2;
+
+        value
+    }
+
+    fn read_constant(&mut self) -> Value {
+        let index = self.read_byte() as usize;
+        self.frame().chunk.constants[index]
+    }
+
+    fn read_string(&mut self) -> ObjRef {
+        match self.read_constant() {
+            Value::Object(handle) => handle,
+            _ => unreachable!("Names are string constants"),
+        }
+    }
+
+    fn undefined_variable(&self, name: ObjRef) -> Error {
+        Error::UndefinedVariable {
+            name: self.heap.string(name).to_string(),
+        }
+    }
+
+    fn undefined_property(&self, name: ObjRef) -> Error {
+        Error::UndefinedProperty {
+            name: self.heap.string(name).to_string(),
+        }
+    }
+
+    fn run(&mut self) -> Result<()> {
+        loop {
+            if self.trace {
+                self.trace_instruction();
+            }
+
+            let op = OpCode::try_from(self.read_byte()).expect("The compiler emits valid opcodes");
+
+            match op {
+                OpCode::Constant => {
+                    let constant = self.read_constant();
+                    self.push(constant);
+                }
+                OpCode::Nil => self.push(Value::Nil),
+                OpCode::True => self.push(Value::Bool(true)),
+                OpCode::False => self.push(Value::Bool(false)),
+                OpCode::Pop => {
+                    self.pop();
+                }
+                OpCode::GetLocal => {
+                    let slot = self.frame().slots + self.read_byte() as usize;
+                    self.push(self.stack[slot]);
+                }
+                OpCode::SetLocal => {
+                    let slot = self.frame().slots + self.read_byte() as usize;
+                    self.stack[slot] = self.peek(0);
+                }
+                OpCode::GetGlobal => {
+                    let name = self.read_string();
+                    match self.globals.get(&name) {
+                        Some(value) => self.push(*value),
+                        None => return Err(self.undefined_variable(name)),
+                    }
+                }
+                OpCode::DefineGlobal => {
+                    let name = self.read_string();
+                    let value = self.pop();
+                    self.globals.insert(name, value);
+                }
+                OpCode::SetGlobal => {
+                    let name = self.read_string();
+                    let value = self.peek(0);
+                    match self.globals.get_mut(&name) {
+                        Some(global) => *global = value,
+                        None => return Err(self.undefined_variable(name)),
+                    }
+                }
+                OpCode::GetUpvalue => {
+                    let upvalue = self.frame_upvalue();
+                    let value = match self.heap.upvalue(upvalue) {
+                        Upvalue::Open(slot) => self.stack[*slot],
+                        Upvalue::Closed(value) => *value,
+                    };
+                    self.push(value);
+                }
+                OpCode::SetUpvalue => {
+                    let upvalue = self.frame_upvalue();
+                    let value = self.peek(0);
+                    match self.heap.upvalue_mut(upvalue) {
+                        Upvalue::Open(slot) => self.stack[*slot] = value,
+                        Upvalue::Closed(closed) => *closed = value,
+                    }
+                }
+                OpCode::GetProperty => {
+                    let name = self.read_string();
+                    let Some(instance) = self.as_instance(self.peek(0)) else {
+                        return Err(Error::NotAnInstance);
+                    };
+
+                    let (field, class) = match self.heap.get(instance) {
+                        Object::Instance(instance) => {
+                            (instance.fields.get(&name).copied(), instance.class)
+                        }
+                        _ => unreachable!("We just checked"),
+                    };
+
+                    match field {
+                        Some(value) => {
+                            self.pop();
+                            self.push(value);
+                        }
+                        None => self.bind_method(class, name)?,
+                    }
+                }
+                OpCode::SetProperty => {
+                    let name = self.read_string();
+                    let Some(instance) = self.as_instance(self.peek(1)) else {
+                        return Err(Error::NotAnInstanceField);
+                    };
+
+                    let value = self.peek(0);
+                    self.update(instance, |object| {
+                        if let Object::Instance(instance) = object {
+                            instance.fields.insert(name, value);
+                        }
+                    });
+
+                    let value = self.pop();
+                    self.pop();
+                    self.push(value);
+                }
+                OpCode::GetSuper => {
+                    let name = self.read_string();
+                    let Value::Object(superclass) = self.pop() else {
+                        unreachable!("`super` is always a class")
+                    };
+                    self.bind_method(superclass, name)?;
+                }
+                OpCode::Equal => {
+                    let b = self.pop();
+                    let a = self.pop();
+                    self.push(Value::Bool(a == b));
+                }
+                OpCode::Greater => self.binary_number(|a, b| Value::Bool(a > b))?,
+                OpCode::Less => self.binary_number(|a, b| Value::Bool(a < b))?,
+                OpCode::Add => self.add()?,
+                OpCode::Subtract => self.binary_number(|a, b| Value::Number(a - b))?,
+                OpCode::Multiply => self.binary_number(|a, b| Value::Number(a * b))?,
+                OpCode::Divide => self.binary_number(|a, b| Value::Number(a / b))?,
+                OpCode::Not => {
+                    let value = self.pop();
+                    self.push(Value::Bool(value.is_falsey()));
+                }
+                OpCode::Negate => match self.peek(0) {
+                    Value::Number(n) => {
+                        self.pop();
+                        self.push(Value::Number(-n));
+                    }
+                    _ => return Err(Error::InvalidUnaryOperand),
+                },
+                OpCode::Interpolate => {
+                    let count = self.read_byte() as usize;
+                    let first = self.stack.len() - count;
+
+                    let mut string = String::new();
+                    for &value in &self.stack[first..] {
+                        let _ = write!(string, "{}", self.heap.display(value));
+                    }
+
+                    // The parts stay on the stack until the result exists.
+                    let result = self.intern(&string);
+                    self.stack.truncate(first);
+                    self.push(Value::Object(result));
+                }
+                OpCode::Print => {
+                    let value = self.pop();
+                    println!("{}", self.heap.display(value));
+                }
+                OpCode::Jump => {
+                    let offset = self.read_u16() as usize;
+                    self.frame_mut().ip += offset;
+                }
+                OpCode::JumpIfFalse => {
+                    let offset = self.read_u16() as usize;
+                    if self.peek(0).is_falsey() {
+                        self.frame_mut().ip += offset;
+                    }
+                }
+                OpCode::Loop => {
+                    let offset = self.read_u16() as usize;
+                    self.frame_mut().ip -= offset;
+                }
+                OpCode::Call => {
+                    let argument_count = self.read_byte() as usize;
+                    self.call_value(self.peek(argument_count), argument_count)?;
+                }
+                OpCode::Invoke => {
+                    let name = self.read_string();
+                    let argument_count = self.read_byte() as usize;
+                    self.invoke(name, argument_count)?;
+                }
+                OpCode::SuperInvoke => {
+                    let name = self.read_string();
+                    let argument_count = self.read_byte() as usize;
+                    let Value::Object(superclass) = self.pop() else {
+                        unreachable!("`super` is always a class")
+                    };
+                    self.invoke_from_class(superclass, name, argument_count)?;
+                }
+                OpCode::Closure => {
+                    let Value::Object(function) = self.read_constant() else {
+                        unreachable!("Closures are built from function constants")
+                    };
+                    let upvalue_count = self.heap.function(function).upvalue_count;
+
+                    let mut upvalues = Vec::with_capacity(upvalue_count);
+                    for _ in 0..upvalue_count {
+                        let is_local = self.read_byte() == 1;
+                        let index = self.read_byte() as usize;
+
+                        let upvalue = if is_local {
+                            self.capture_upvalue(self.frame().slots + index)
+                        } else {
+                            self.heap.closure(self.frame().closure).upvalues[index]
+                        };
+                        upvalues.push(upvalue);
+                    }
+
+                    let closure = self.alloc(Object::Closure(Closure { function, upvalues }));
+                    self.push(Value::Object(closure));
+                }
+                OpCode::CloseUpvalue => {
+                    self.close_upvalues(self.stack.len() - 1);
+                    self.pop();
+                }
+                OpCode::Return => {
+                    let result = self.pop();
+                    let frame = self.frames.pop().expect("There is always a frame running");
+                    self.close_upvalues(frame.slots);
+
+                    self.stack.truncate(frame.slots);
+                    if self.frames.is_empty() {
+                        return Ok(());
+                    }
+                    self.push(result);
+                }
+                OpCode::Class => {
+                    let name = self.read_string();
+                    let class = self.alloc(Object::Class(Class {
+                        name: self.heap.string(name).to_string(),
+                        methods: HashMap::new(),
+                    }));
+                    self.push(Value::Object(class));
+                }
+                OpCode::Inherit => {
+                    let superclass = match self.peek(1) {
+                        Value::Object(handle)
+                            if matches!(self.heap.get(handle), Object::Class(_)) =>
+                        {
+                            handle
+                        }
+                        _ => return Err(Error::SuperclassNotAClass),
+                    };
+                    let Value::Object(subclass) = self.peek(0) else {
+                        unreachable!("The class was just created")
+                    };
+
+                    // Copy-down inheritance: methods defined by the subclass
+                    // later simply overwrite the inherited ones.
+                    let methods = self.heap.class(superclass).methods.clone();
+                    self.update(subclass, |object| {
+                        if let Object::Class(class) = object {
+                            class.methods.extend(methods);
+                        }
+                    });
+                    self.pop();
+                }
+                OpCode::Method => {
+                    let name = self.read_string();
+                    let Value::Object(method) = self.peek(0) else {
+                        unreachable!("Methods are closures")
+                    };
+                    let Value::Object(class) = self.peek(1) else {
+                        unreachable!("Methods are defined on a class")
+                    };
+
+                    self.update(class, |object| {
+                        if let Object::Class(class) = object {
+                            class.methods.insert(name, method);
+                        }
+                    });
+                    self.pop();
+                }
+            }
+        }
+    }
+
+    fn frame_upvalue(&mut self) -> ObjRef {
+        let index = self.read_byte() as usize;
+        self.heap.closure(self.frame().closure).upvalues[index]
+    }
+
+    fn as_instance(&self, value: Value) -> Option<ObjRef> {
+        match value {
+            Value::Object(handle) if matches!(self.heap.get(handle), Object::Instance(_)) => {
+                Some(handle)
+            }
+            _ => None,
+        }
+    }
+
+    fn binary_number(&mut self, operation: fn(f64, f64) -> Value) -> Result<()> {
+        match (self.peek(1), self.peek(0)) {
+            (Value::Number(a), Value::Number(b)) => {
+                self.pop();
+                self.pop();
+                self.push(operation(a, b));
+
+                Ok(())
+            }
+            _ => Err(Error::InvalidBinaryOperands { strings: false }),
+        }
+    }
+
+    fn add(&mut self) -> Result<()> {
+        let result = match (self.peek(1), self.peek(0)) {
+            (Value::Number(a), Value::Number(b)) => Value::Number(a + b),
+            (Value::Object(a), Value::Object(b)) => {
+                let concatenated = match (self.heap.get(a), self.heap.get(b)) {
+                    (Object::String(a), Object::String(b)) => format!("{a}{b}"),
+                    _ => return Err(Error::InvalidBinaryOperands { strings: true }),
+                };
+
+                // Both operands stay on the stack until the result is
+                // allocated, so a collection cannot free them.
+                Value::Object(self.intern(&concatenated))
+            }
+            _ => return Err(Error::InvalidBinaryOperands { strings: true }),
+        };
+
+        self.pop();
+        self.pop();
+        self.push(result);
+
+        Ok(())
+    }
+
+    fn call_value(&mut self, callee: Value, argument_count: usize) -> Result<()> {
+        let Value::Object(handle) = callee else {
+            return Err(Error::NotCallable);
+        };
+
+        match self.heap.get(handle) {
+            Object::Closure(_) => self.call(handle, argument_count),
+            Object::Native(native) => {
+                if argument_count != native.arity {
+                    return Err(Error::ArityMismatch {
+                        expected: native.arity,
+                        found: argument_count,
+                    });
+                }
+
+                let native = native.clone();
+                let arguments =
+                    convert_arguments(&self.heap, &self.stack[self.stack.len() - argument_count..]);
+                let result = arguments
+                    .and_then(|arguments| native.call(&arguments))
+                    .map_err(|message| Error::NativeFailure {
+                        name: native.name.clone(),
+                        message,
+                    })?;
+
+                let result = match result {
+                    NativeValue::Nil => Value::Nil,
+                    NativeValue::Bool(b) => Value::Bool(b),
+                    NativeValue::Number(n) => Value::Number(n),
+                    NativeValue::String(s) => Value::Object(self.intern(&s)),
+                };
+
+                self.stack.truncate(self.stack.len() - argument_count - 1);
+                self.push(result);
+
+                Ok(())
+            }
+            Object::Class(class) => {
+                let initializer = class.methods.get(&self.init_string).copied();
+
+                let instance = self.alloc(Object::Instance(Instance {
+                    class: handle,
+                    fields: HashMap::new(),
+                }));
+                let slot = self.stack.len() - argument_count - 1;
+                self.stack[slot] = Value::Object(instance);
+
+                match initializer {
+                    Some(initializer) => self.call(initializer, argument_count),
+                    None if argument_count != 0 => Err(Error::ArityMismatch {
+                        expected: 0,
+                        found: argument_count,
+                    }),
+                    None => Ok(()),
+                }
+            }
+            Object::BoundMethod(BoundMethod { receiver, method }) => {
+                let method = *method;
+                let slot = self.stack.len() - argument_count - 1;
+                self.stack[slot] = *receiver;
+
+                self.call(method, argument_count)
+            }
+            _ => Err(Error::NotCallable),
+        }
+    }
+
+    fn call(&mut self, closure: ObjRef, argument_count: usize) -> Result<()> {
+        let function = self.heap.function(self.heap.closure(closure).function);
+
+        if argument_count != function.arity {
+            return Err(Error::ArityMismatch {
+                expected: function.arity,
+                found: argument_count,
+            });
+        }
+
+        if self.frames.len() == FRAMES_MAX {
+            return Err(Error::StackOverflow);
+        }
+
+        let frame = CallFrame {
+            closure,
+            chunk: Rc::clone(&function.chunk),
+            ip: 0,
+            slots: self.stack.len() - argument_count - 1,
+        };
+        self.frames.push(frame);
+
+        Ok(())
+    }
+
+    fn invoke(&mut self, name: ObjRef, argument_count: usize) -> Result<()> {
+        let Some(instance) = self.as_instance(self.peek(argument_count)) else {
+            return Err(Error::NotAnInstance);
+        };
+
+        let (field, class) = match self.heap.get(instance) {
+            Object::Instance(instance) => (instance.fields.get(&name).copied(), instance.class),
+            _ => unreachable!("We just checked"),
+        };
+
+        // A field holding a function shadows a method of the same name.
+        if let Some(field) = field {
+            let slot = self.stack.len() - argument_count - 1;
+            self.stack[slot] = field;
+            return self.call_value(field, argument_count);
+        }
+
+        self.invoke_from_class(class, name, argument_count)
+    }
+
+    fn invoke_from_class(
+        &mut self,
+        class: ObjRef,
+        name: ObjRef,
+        argument_count: usize,
+    ) -> Result<()> {
+        match self.heap.class(class).methods.get(&name).copied() {
+            Some(method) => self.call(method, argument_count),
+            None => Err(self.undefined_property(name)),
+        }
+    }
+
+    /// Replaces the receiver on top of the stack with its method `name`.
+    fn bind_method(&mut self, class: ObjRef, name: ObjRef) -> Result<()> {
+        let Some(method) = self.heap.class(class).methods.get(&name).copied() else {
+            return Err(self.undefined_property(name));
+        };
+
+        let bound = self.alloc(Object::BoundMethod(BoundMethod {
+            receiver: self.peek(0),
+            method,
+        }));
+        self.pop();
+        self.push(Value::Object(bound));
+
+        Ok(())
+    }
+
+    fn capture_upvalue(&mut self, slot: usize) -> ObjRef {
+        let existing =
+            self.open_upvalues.iter().copied().find(
+                |&upvalue| matches!(self.heap.upvalue(upvalue), Upvalue::Open(s) if *s == slot),
+            );
+
+        if let Some(upvalue) = existing {
+            return upvalue;
+        }
+
+        let upvalue = self.alloc(Object::Upvalue(Upvalue::Open(slot)));
+        self.open_upvalues.push(upvalue);
+
+        upvalue
+    }
+
+    /// Moves the values of every upvalue pointing at `last` or above off the
+    /// stack and into the upvalue itself.
+    fn close_upvalues(&mut self, last: usize) {
+        let mut index = 0;
+        while index < self.open_upvalues.len() {
+            let upvalue = self.open_upvalues[index];
+
+            match *self.heap.upvalue(upvalue) {
+                Upvalue::Open(slot) if slot >= last => {
+                    *self.heap.upvalue_mut(upvalue) = Upvalue::Closed(self.stack[slot]);
+                    self.open_upvalues.swap_remove(index);
+                }
+                _ => index += 1,
+            }
+        }
+    }
+}
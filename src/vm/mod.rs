@@ -0,0 +1,105 @@
+mod heap;
+mod machine;
+mod native;
+mod object;
+mod value;
+
+pub use heap::{Heap, ObjRef};
+pub use machine::Vm;
+pub use object::{Function, Object};
+pub use value::Value;
+
+use crate::{
+    diagnostics::{Diagnostic, ToDiagnostic},
+    evaluate::TraceLine,
+    primitives::Span,
+};
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// The same errors the tree-walking interpreter reports, with the same
+/// wording, so both backends can be checked against one test suite.
+pub enum Error {
+    InvalidUnaryOperand,
+    /// `strings` is set when `+` was used, which also accepts two strings.
+    InvalidBinaryOperands {
+        strings: bool,
+    },
+    UndefinedVariable {
+        name: String,
+    },
+    NotCallable,
+    NotAnInstance,
+    SuperclassNotAClass,
+    NotAnInstanceField,
+    UndefinedProperty {
+        name: String,
+    },
+    NativeFailure {
+        name: String,
+        message: String,
+    },
+    ArityMismatch {
+        expected: usize,
+        found: usize,
+    },
+    StackOverflow,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidUnaryOperand => write!(f, "Operand must be a number."),
+            Error::InvalidBinaryOperands { strings: true } => {
+                write!(f, "Operands must be two numbers or two strings.")
+            }
+            Error::InvalidBinaryOperands { strings: false } => {
+                write!(f, "Operands must be numbers.")
+            }
+            Error::UndefinedVariable { name } => write!(f, "Undefined variable '{name}'."),
+            Error::NotCallable => write!(f, "Can only call functions and classes."),
+            Error::NotAnInstance => write!(f, "Only instances have properties."),
+            Error::SuperclassNotAClass => write!(f, "Superclass must be a class."),
+            Error::NotAnInstanceField => write!(f, "Only instances have fields."),
+            Error::UndefinedProperty { name } => write!(f, "Undefined property '{name}'."),
+            Error::NativeFailure { name, message } => write!(f, "{name}: {message}"),
+            Error::ArityMismatch { expected, found } => {
+                write!(f, "Expected {expected} arguments but got {found}.")
+            }
+            Error::StackOverflow => write!(f, "Stack overflow."),
+        }
+    }
+}
+
+/// An error that stopped the VM, with the span of the failing instruction
+/// and the active calls, innermost first.
+pub struct RuntimeError {
+    pub error: Error,
+    pub span: Span,
+    pub trace: Vec<TraceLine>,
+}
+
+impl std::fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.error)?;
+        for line in &self.trace {
+            write!(f, "\n{line}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ToDiagnostic for RuntimeError {
+    fn diagnostic(&self) -> Diagnostic {
+        let diagnostic = Diagnostic::error(self.error.to_string(), self.span);
+
+        if self.trace.len() < 2 {
+            return diagnostic;
+        }
+
+        self.trace.iter().fold(diagnostic, |diagnostic, line| {
+            diagnostic.with_note(line.to_string())
+        })
+    }
+}
@@ -0,0 +1,20 @@
+use crate::native::{NativeValue, UNSUPPORTED_ARGUMENT};
+
+use super::{Heap, Object, Value};
+
+/// Converts the VM arguments of a native call. Strings are copied out of the
+/// heap.
+pub fn convert_arguments(heap: &Heap, arguments: &[Value]) -> Result<Vec<NativeValue>, String> {
+    arguments
+        .iter()
+        .map(|&argument| match argument {
+            Value::Nil => Ok(NativeValue::Nil),
+            Value::Bool(b) => Ok(NativeValue::Bool(b)),
+            Value::Number(n) => Ok(NativeValue::Number(n)),
+            Value::Object(handle) => match heap.get(handle) {
+                Object::String(string) => Ok(NativeValue::String(string.clone())),
+                _ => Err(UNSUPPORTED_ARGUMENT.to_string()),
+            },
+        })
+        .collect()
+}
@@ -0,0 +1,120 @@
+use std::{collections::HashMap, rc::Rc};
+
+use crate::{chunk::Chunk, native::Native, primitives::Span};
+
+use super::{ObjRef, Value};
+
+pub enum Object {
+    /// Shared with the heap's intern table.
+    String(Rc<str>),
+    Function(Function),
+    Native(Native),
+    Closure(Closure),
+    Upvalue(Upvalue),
+    Class(Class),
+    Instance(Instance),
+    BoundMethod(BoundMethod),
+}
+
+impl Object {
+    /// An estimate of the memory the object owns, used to decide when to
+    /// collect garbage. Containers count their capacity, not their length.
+    pub fn size(&self) -> usize {
+        use std::mem::size_of;
+
+        let owned = match self {
+            Object::String(string) => string.len(),
+            Object::Function(function) => {
+                function.chunk.code.capacity()
+                    + function.chunk.spans.capacity() * size_of::<Span>()
+                    + function.chunk.constants.capacity() * size_of::<Value>()
+                    + function.name.as_ref().map_or(0, String::capacity)
+            }
+            Object::Native(native) => native.name.capacity(),
+            Object::Closure(closure) => closure.upvalues.capacity() * size_of::<ObjRef>(),
+            Object::Upvalue(_) => 0,
+            Object::Class(class) => {
+                class.name.capacity() + class.methods.capacity() * size_of::<(ObjRef, ObjRef)>()
+            }
+            Object::Instance(instance) => instance.fields.capacity() * size_of::<(ObjRef, Value)>(),
+            Object::BoundMethod(_) => 0,
+        };
+
+        size_of::<Object>() + owned
+    }
+
+    /// Calls `visit` with every value the object keeps alive.
+    pub fn references(&self, mut visit: impl FnMut(Value)) {
+        match self {
+            Object::String(_) | Object::Native(_) => {}
+            Object::Function(function) => function.chunk.constants.iter().copied().for_each(visit),
+            Object::Closure(closure) => {
+                visit(Value::Object(closure.function));
+                closure
+                    .upvalues
+                    .iter()
+                    .for_each(|&upvalue| visit(Value::Object(upvalue)));
+            }
+            Object::Upvalue(Upvalue::Open(_)) => {}
+            Object::Upvalue(Upvalue::Closed(value)) => visit(*value),
+            Object::Class(class) => {
+                for (&name, &method) in &class.methods {
+                    visit(Value::Object(name));
+                    visit(Value::Object(method));
+                }
+            }
+            Object::Instance(instance) => {
+                visit(Value::Object(instance.class));
+                for (&name, &value) in &instance.fields {
+                    visit(Value::Object(name));
+                    visit(value);
+                }
+            }
+            Object::BoundMethod(bound) => {
+                visit(bound.receiver);
+                visit(Value::Object(bound.method));
+            }
+        }
+    }
+}
+
+pub struct Function {
+    pub arity: usize,
+    pub upvalue_count: usize,
+    /// Shared with the call frames running it, so the VM can read the code
+    /// without going through the heap on every instruction.
+    pub chunk: Rc<Chunk>,
+    /// `None` for the top level script.
+    pub name: Option<String>,
+}
+
+/// A function together with the variables it captured.
+pub struct Closure {
+    pub function: ObjRef,
+    pub upvalues: Vec<ObjRef>,
+}
+
+/// A captured variable. It points into the stack while the variable is in
+/// scope and holds the value itself once the scope is left.
+pub enum Upvalue {
+    Open(usize),
+    Closed(Value),
+}
+
+pub struct Class {
+    pub name: String,
+    /// Method closures by their interned name, including the inherited
+    /// ones.
+    pub methods: HashMap<ObjRef, ObjRef>,
+}
+
+pub struct Instance {
+    pub class: ObjRef,
+    /// Keyed by the interned field name.
+    pub fields: HashMap<ObjRef, Value>,
+}
+
+pub struct BoundMethod {
+    pub receiver: Value,
+    pub method: ObjRef,
+}
@@ -0,0 +1,18 @@
+use super::ObjRef;
+
+/// A value on the VM stack. Everything that is not a number, boolean or nil
+/// lives on the heap and is referred to by handle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Nil,
+    Bool(bool),
+    Number(f64),
+    Object(ObjRef),
+}
+
+impl Value {
+    /// `false` and `nil` are falsey, every other value is truthy.
+    pub fn is_falsey(self) -> bool {
+        matches!(self, Value::Bool(false) | Value::Nil)
+    }
+}
@@ -0,0 +1,40 @@
+//! Runs every script under `tests/diagnostics` with `--error-format rich` and
+//! compares stderr against the `.stderr` file next to it.
+
+use std::{fs, path::Path, process::Command};
+
+#[test]
+fn rich_diagnostics() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/diagnostics");
+
+    let mut found: Vec<_> = fs::read_dir(&dir)
+        .expect("The test directory exists")
+        .map(|entry| entry.expect("The entry can be read").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lox"))
+        .collect();
+    found.sort();
+
+    let mut failures = Vec::new();
+
+    for path in found {
+        let expected = fs::read_to_string(path.with_extension("stderr"))
+            .expect("Every script has a .stderr file");
+
+        let output = Command::new(env!("CARGO_BIN_EXE_codecrafters-interpreter"))
+            .args(["run", "--error-format", "rich"])
+            .arg(&path)
+            .output()
+            .expect("The interpreter can be spawned");
+
+        let actual = String::from_utf8_lossy(&output.stderr);
+
+        if actual != expected {
+            failures.push(format!(
+                "{}\n--- expected\n{expected}--- actual\n{actual}",
+                path.display()
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
@@ -0,0 +1,40 @@
+//! Disassembles every script under `tests/disassemble` and compares the
+//! listing against the `.out` file next to it.
+
+use std::{fs, path::Path, process::Command};
+
+#[test]
+fn disassembly_listings() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/disassemble");
+
+    let mut found: Vec<_> = fs::read_dir(&dir)
+        .expect("The test directory exists")
+        .map(|entry| entry.expect("The entry can be read").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lox"))
+        .collect();
+    found.sort();
+
+    let mut failures = Vec::new();
+
+    for path in found {
+        let expected =
+            fs::read_to_string(path.with_extension("out")).expect("Every script has a .out file");
+
+        let output = Command::new(env!("CARGO_BIN_EXE_codecrafters-interpreter"))
+            .arg("disassemble")
+            .arg(&path)
+            .output()
+            .expect("The interpreter can be spawned");
+
+        let actual = String::from_utf8_lossy(&output.stdout);
+
+        if !output.status.success() || actual != expected {
+            failures.push(format!(
+                "{}\n--- expected\n{expected}--- actual\n{actual}",
+                path.display()
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
@@ -0,0 +1,63 @@
+//! Runs `tests/gc/ring.lox` on the VM with `--gc-stress --gc-log` and reads
+//! the log to check that the collector actually frees unreachable cycles.
+
+use std::{path::Path, process::Command};
+
+/// One `-- gc: freed B bytes in N objects (before -> after), next at M` line.
+struct Collection {
+    freed_bytes: usize,
+    freed_objects: usize,
+    bytes_before: usize,
+    bytes_after: usize,
+}
+
+fn parse(line: &str) -> Option<Collection> {
+    let words: Vec<_> = line
+        .strip_prefix("-- gc: freed ")?
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.parse().expect("Only digits are left"))
+        .collect();
+
+    match words[..] {
+        [freed_bytes, freed_objects, bytes_before, bytes_after, _next] => Some(Collection {
+            freed_bytes,
+            freed_objects,
+            bytes_before,
+            bytes_after,
+        }),
+        _ => None,
+    }
+}
+
+#[test]
+fn unreachable_cycles_are_freed() {
+    let output = Command::new(env!("CARGO_BIN_EXE_codecrafters-interpreter"))
+        .args(["run", "--backend", "vm", "--gc-stress", "--gc-log"])
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/gc/ring.lox"))
+        .output()
+        .expect("The interpreter can be spawned");
+
+    assert!(output.status.success(), "status: {}", output.status);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "true\nab\n");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let collections: Vec<_> = stderr
+        .lines()
+        .map(|line| parse(line).unwrap_or_else(|| panic!("Not a gc log line: {line}")))
+        .collect();
+
+    for collection in &collections {
+        assert_eq!(
+            collection.bytes_before - collection.bytes_after,
+            collection.freed_bytes
+        );
+    }
+
+    assert!(
+        collections
+            .iter()
+            .any(|collection| collection.freed_objects >= 100),
+        "No collection freed the ring of 100 instances:\n{stderr}"
+    );
+}
@@ -0,0 +1,101 @@
+//! Runs every script under `tests/lox` on every backend and compares what it
+//! prints against the `// expect: <output>` comments written next to each
+//! statement. A `// expect runtime error: <message>` comment means the script
+//! must stop with that message and exit code 70, and a
+//! `// expect compile error: <line>` comment that it is rejected before it
+//! runs, printing that line first and exiting with 65. The VM also runs
+//! every script with `--gc-stress`, to catch objects freed while still in
+//! use.
+
+use std::{fs, path::Path, process::Command};
+
+const EXPECT: &str = "// expect: ";
+const EXPECT_RUNTIME_ERROR: &str = "// expect runtime error: ";
+const EXPECT_COMPILE_ERROR: &str = "// expect compile error: ";
+const CONFIGURATIONS: [&[&str]; 3] = [
+    &["--backend", "tree-walk"],
+    &["--backend", "vm"],
+    &["--backend", "vm", "--gc-stress"],
+];
+
+fn scripts(dir: &Path, found: &mut Vec<std::path::PathBuf>) {
+    for entry in fs::read_dir(dir).expect("The test directory exists") {
+        let path = entry.expect("The entry can be read").path();
+
+        if path.is_dir() {
+            scripts(&path, found);
+        } else if path.extension().is_some_and(|ext| ext == "lox") {
+            found.push(path);
+        }
+    }
+}
+
+fn expected_output(source: &str) -> Vec<&str> {
+    source
+        .lines()
+        .filter_map(|line| line.split_once(EXPECT).map(|(_, expected)| expected))
+        .collect()
+}
+
+/// The first line of stderr and the exit code a failing script must end
+/// with.
+fn expected_error(source: &str) -> Option<(&str, i32)> {
+    source.lines().find_map(|line| {
+        if let Some((_, message)) = line.split_once(EXPECT_RUNTIME_ERROR) {
+            return Some((message, 70));
+        }
+
+        line.split_once(EXPECT_COMPILE_ERROR)
+            .map(|(_, message)| (message, 65))
+    })
+}
+
+#[test]
+fn lox_scripts() {
+    let mut found = Vec::new();
+    scripts(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/lox"),
+        &mut found,
+    );
+    found.sort();
+
+    let mut failures = Vec::new();
+
+    for path in found {
+        let source = fs::read_to_string(&path).expect("The script can be read");
+        let expected = expected_output(&source);
+        let error = expected_error(&source);
+
+        for configuration in CONFIGURATIONS {
+            let output = Command::new(env!("CARGO_BIN_EXE_codecrafters-interpreter"))
+                .args(["run", "--error-format", "short"])
+                .args(configuration)
+                .arg(&path)
+                .output()
+                .expect("The interpreter can be spawned");
+
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let actual: Vec<_> = stdout.lines().collect();
+
+            let error_matches = match error {
+                Some((message, code)) => {
+                    output.status.code() == Some(code) && stderr.lines().next() == Some(message)
+                }
+                None => output.status.success(),
+            };
+
+            if actual != expected || !error_matches {
+                failures.push(format!(
+                    "{} ({})\n  expected: {expected:?}\n  actual:   {actual:?}\n  status:   {}\n  stderr:   {}",
+                    path.display(),
+                    configuration.join(" "),
+                    output.status,
+                    stderr.trim_end(),
+                ));
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
@@ -0,0 +1,113 @@
+//! Pipes entries into the `repl` subcommand and checks what it prints. The
+//! prompts are written to stdout too, so `> ` marks where each entry was read
+//! and `... ` every continuation line.
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// Runs a session fed with `input` and returns its stdout and stderr.
+fn repl(input: &str) -> (String, String) {
+    repl_with_format("short", input)
+}
+
+fn repl_with_format(format: &str, input: &str) -> (String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_codecrafters-interpreter"))
+        .args(["repl", "--error-format", format])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("The interpreter can be spawned");
+
+    child
+        .stdin
+        .take()
+        .expect("Stdin is piped")
+        .write_all(input.as_bytes())
+        .expect("The input can be written");
+
+    let output = child.wait_with_output().expect("The session ends at EOF");
+    assert!(output.status.success(), "status: {}", output.status);
+
+    (
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+        String::from_utf8_lossy(&output.stderr).into_owned(),
+    )
+}
+
+#[test]
+fn globals_persist_between_entries() {
+    let (stdout, stderr) = repl("var a = 1;\nfun double(n) { return n * 2; }\nprint double(a);\n");
+
+    assert_eq!(stdout, "> > > 2\n> \n");
+    assert_eq!(stderr, "");
+}
+
+#[test]
+fn bare_expressions_are_echoed() {
+    let (stdout, stderr) = repl("1 + 2\n\"with semicolon\";\nvar quiet = 3;\nquiet\n");
+
+    assert_eq!(stdout, "> 3\n> with semicolon\n> > 3\n> \n");
+    assert_eq!(stderr, "");
+}
+
+#[test]
+fn unbalanced_entries_continue_on_the_next_line() {
+    let (stdout, stderr) = repl("fun f() {\n  return (1 +\n  2);\n}\nprint f();\n");
+
+    assert_eq!(stdout, "> ... ... ... > 3\n> \n");
+    assert_eq!(stderr, "");
+}
+
+#[test]
+fn unterminated_strings_continue_on_the_next_line() {
+    let (stdout, stderr) = repl("print \"two\nlines\";\n");
+
+    assert_eq!(stdout, "> ... two\nlines\n> \n");
+    assert_eq!(stderr, "");
+}
+
+#[test]
+fn errors_do_not_end_the_session() {
+    let (stdout, stderr) = repl("var a = 1;\n@\nprint ;\n-\"x\";\nprint a;\n");
+
+    assert_eq!(stdout, "> > > > > 1\n> \n");
+    assert_eq!(
+        stderr,
+        "[line 2] Error: Unexpected character: @\n\
+         [line 3] Error at ';': Expect expression.\n\
+         Operand must be a number.\n\
+         [line 4] in script\n"
+    );
+}
+
+#[test]
+fn errors_point_into_the_entry_that_declared_the_function() {
+    let (_, stderr) = repl_with_format(
+        "rich",
+        "fun f() { return -\"x\"; }\nvar someLongVariableName = 1; f();\n",
+    );
+
+    assert_eq!(
+        stderr,
+        "error: Operand must be a number.\n \
+         --> 1:18\n  \
+         |\n\
+         1 | fun f() { return -\"x\"; }\n  \
+         |                  ^\n  \
+         |                   --- operand is a string here\n  \
+         = note: [line 1] in f()\n  \
+         = note: [line 2] in script\n\n"
+    );
+}
+
+#[test]
+fn stack_overflows_do_not_end_the_session() {
+    let (stdout, stderr) = repl("var a = 1;\nfun f() { return f(); }\nf();\nprint a;\n");
+
+    assert_eq!(stdout, "> > > > 1\n> \n");
+    assert_eq!(stderr.lines().next(), Some("Stack overflow."));
+    assert_eq!(stderr.lines().last(), Some("[line 3] in script"));
+}